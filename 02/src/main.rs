@@ -1,20 +1,66 @@
-use color_eyre::eyre::Result;
+use std::str::FromStr;
+
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Report, Result,
+};
+use rand::{distributions::WeightedIndex, prelude::*};
 
 fn main() -> Result<()> {
     let input = libaoc::init()?;
+    let options = libaoc::options();
+    let columns = options.parse("--columns")?.unwrap_or_default();
+    let rules = match options.value("--rules") {
+        Some(path) => ScoringRules::load(path)?,
+        None => ScoringRules::default(),
+    };
 
-    let game = parse(&input);
-    let total = score(&game);
+    let game = parse(&input, &columns)?;
+    let total = score(&game, &rules)?;
 
     println!("The total score is {total}");
 
-    let total = score(&to_game(&parse_outcome(&input)));
+    let total = score(&to_game(&parse_outcome(&input, &columns)?), &rules)?;
     println!("The total score is {total}");
 
+    if options.flag("--analyze") {
+        let analysis = analyze(&input, &columns, &rules)?;
+        println!(
+            "Reading the second column as shapes scores {}",
+            analysis.as_shapes
+        );
+        println!(
+            "Reading the second column as outcomes scores {}",
+            analysis.as_outcomes
+        );
+        println!("The best possible score is {}", analysis.optimal);
+        for (n, (opponent, me)) in analysis.best_responses.iter().enumerate() {
+            println!("Round {}: play {me:?} against {opponent:?}", n + 1);
+        }
+
+        let opponent = options.parse("--opponent")?.unwrap_or_default();
+        let trials = options.parse("--trials")?.unwrap_or(10_000);
+        let mut rng = match options.parse("--seed")? {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        for (reading, game) in [
+            ("shapes", parse(&input, &columns)?),
+            ("outcomes", to_game(&parse_outcome(&input, &columns)?)),
+        ] {
+            let responses: Vec<_> = game.into_iter().map(|(_, me)| me).collect();
+            let Estimate { mean, variance } =
+                simulate(&responses, &opponent, &rules, trials, &mut rng)?;
+            println!(
+                "Reading as {reading} against {opponent} scores {mean:.1} on average (variance {variance:.1})"
+            );
+        }
+    }
+
     Ok(())
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Shape {
     Rock,
     Paper,
@@ -28,57 +74,136 @@ enum Outcome {
     Win,
 }
 
-impl From<char> for Outcome {
-    fn from(c: char) -> Self {
+impl TryFrom<char> for Outcome {
+    type Error = Report;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
         match c {
-            'X' => Self::Lose,
-            'Y' => Self::Draw,
-            'Z' => Self::Win,
-            _ => unreachable!(),
+            'X' => Ok(Self::Lose),
+            'Y' => Ok(Self::Draw),
+            'Z' => Ok(Self::Win),
+            _ => Err(eyre!("Invalid outcome {c:?}")),
         }
     }
 }
 
-impl From<char> for Shape {
-    fn from(c: char) -> Self {
+impl TryFrom<char> for Shape {
+    type Error = Report;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
         match c {
-            'A' => Self::Rock,
-            'B' => Self::Paper,
-            'C' => Self::Scissors,
-            'X' => Self::Rock,
-            'Y' => Self::Paper,
-            'Z' => Self::Scissors,
-            _ => unreachable!(),
+            'A' | 'X' => Ok(Self::Rock),
+            'B' | 'Y' => Ok(Self::Paper),
+            'C' | 'Z' => Ok(Self::Scissors),
+            _ => Err(eyre!("Invalid shape {c:?}")),
         }
     }
 }
 
-fn parse(input: &str) -> Vec<(Shape, Shape)> {
-    input
-        .trim()
-        .lines()
-        .map(|l| {
-            (
-                l.chars().next().unwrap().into(),
-                l.chars().nth(2).unwrap().into(),
-            )
-        })
-        .collect()
+/// Letters used by the strategy guide in each of its columns
+///
+/// Both columns list their three letters in the order of the canonical `ABC`
+/// and `XYZ` symbols, e.g. `RPS,RPS` for a guide spelling out the shapes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Columns {
+    first: [char; 3],
+    second: [char; 3],
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Self {
+            first: ['A', 'B', 'C'],
+            second: ['X', 'Y', 'Z'],
+        }
+    }
+}
+
+impl Columns {
+    /// Translates a letter of the given column into its canonical symbol
+    fn decode(&self, column: usize, c: char) -> Option<char> {
+        let (letters, base) = match column {
+            0 => (&self.first, b'A'),
+            _ => (&self.second, b'X'),
+        };
+        letters
+            .iter()
+            .position(|l| *l == c)
+            .map(|i| (base + i as u8) as char)
+    }
 }
 
-fn parse_outcome(input: &str) -> Vec<(Shape, Outcome)> {
-    input
-        .trim()
-        .lines()
-        .map(|l| {
-            (
-                l.chars().next().unwrap().into(),
-                l.chars().nth(2).unwrap().into(),
-            )
+impl FromStr for Columns {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn letters(s: &str) -> Result<[char; 3]> {
+            let letters: Vec<char> = s.trim().chars().collect();
+            let letters: [char; 3] = letters
+                .try_into()
+                .map_err(|_| eyre!("Expected three letters, got {s:?}"))?;
+            if letters[0] == letters[1] || letters[1] == letters[2] || letters[0] == letters[2] {
+                return Err(eyre!("Letters must be distinct in {s:?}"));
+            }
+            Ok(letters)
+        }
+
+        let (first, second) = s
+            .split_once(',')
+            .ok_or_else(|| eyre!("Expected two comma separated columns, got {s:?}"))?;
+        Ok(Self {
+            first: letters(first)?,
+            second: letters(second)?,
         })
+    }
+}
+
+/// Parses a single `<first> <second>` line of the strategy guide
+fn parse_line<T>(line: &str, columns: &Columns) -> Result<(Shape, T)>
+where
+    T: TryFrom<char, Error = Report>,
+{
+    let chars: Vec<char> = line.chars().collect();
+    let symbol = |column: usize| -> Result<char> {
+        let pos = 2 * column + 1;
+        let c = *chars
+            .get(pos - 1)
+            .ok_or_else(|| eyre!("Missing symbol at column {pos}"))?;
+        columns
+            .decode(column, c)
+            .ok_or_else(|| eyre!("Invalid symbol {c:?} at column {pos}"))
+    };
+
+    let first = symbol(0)?;
+    match chars.get(1) {
+        Some(' ') | None => {}
+        Some(c) => return Err(eyre!("Expected space, got {c:?} at column 2")),
+    }
+    let second = symbol(1)?;
+    if let Some(c) = chars.get(3) {
+        return Err(eyre!("Unexpected {c:?} at column 4"));
+    }
+
+    Ok((first.try_into()?, second.try_into()?))
+}
+
+fn parse_guide<T>(input: &str, columns: &Columns) -> Result<Vec<(Shape, T)>>
+where
+    T: TryFrom<char, Error = Report>,
+{
+    libaoc::lines(input)
+        .map(|(n, l)| parse_line(l, columns).wrap_err_with(|| format!("Invalid line {n}")))
         .collect()
 }
 
+fn parse(input: &str, columns: &Columns) -> Result<Vec<(Shape, Shape)>> {
+    parse_guide(input, columns)
+}
+
+fn parse_outcome(input: &str, columns: &Columns) -> Result<Vec<(Shape, Outcome)>> {
+    parse_guide(input, columns)
+}
+
 fn to_game(out: &[(Shape, Outcome)]) -> Vec<(Shape, Shape)> {
     use Outcome::*;
     use Shape::*;
@@ -98,21 +223,246 @@ fn to_game(out: &[(Shape, Outcome)]) -> Vec<(Shape, Shape)> {
         .collect()
 }
 
-fn score(game: &[(Shape, Shape)]) -> i32 {
+impl Shape {
+    const ALL: [Self; 3] = [Self::Rock, Self::Paper, Self::Scissors];
+}
+
+fn outcome(round: &(Shape, Shape)) -> Outcome {
     use Shape::*;
+    match round {
+        (Rock, Paper) | (Paper, Scissors) | (Scissors, Rock) => Outcome::Win,
+        (Rock, Scissors) | (Paper, Rock) | (Scissors, Paper) => Outcome::Lose,
+        _ => Outcome::Draw,
+    }
+}
+
+/// Points awarded for the shapes played and the outcome of each round
+///
+/// Rules are read from lines of `key = value` pairs, where the keys are
+/// `rock`, `paper`, `scissors`, `win`, `draw`, `loss`, `streak_length` and
+/// `streak_bonus`. Missing keys keep their default value, and `#` starts a
+/// comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ScoringRules {
+    shapes: [i32; 3],
+    win: i32,
+    draw: i32,
+    loss: i32,
+    streak: Option<Streak>,
+}
+
+/// Bonus awarded for every win once `length` rounds were won in a row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Streak {
+    length: usize,
+    bonus: i32,
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        Self {
+            shapes: [1, 2, 3],
+            win: 6,
+            draw: 3,
+            loss: 0,
+            streak: None,
+        }
+    }
+}
+
+impl ScoringRules {
+    fn load(path: &str) -> Result<Self> {
+        std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read from {path:?}"))?
+            .parse()
+            .wrap_err_with(|| format!("Invalid scoring rules in {path:?}"))
+    }
+
+    fn round(&self, round: &(Shape, Shape)) -> Result<i32> {
+        let shape = self.shapes[round.1 as usize];
+        shape
+            .checked_add(match outcome(round) {
+                Outcome::Win => self.win,
+                Outcome::Draw => self.draw,
+                Outcome::Lose => self.loss,
+            })
+            .ok_or_else(|| eyre!("Score of {round:?} overflows"))
+    }
+
+    fn streak_bonus(&self, wins: usize) -> i32 {
+        match self.streak {
+            Some(Streak { length, bonus }) if wins >= length => bonus,
+            _ => 0,
+        }
+    }
+}
+
+impl FromStr for ScoringRules {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = Self::default();
+        let (mut length, mut bonus) = (None, None);
+
+        for (n, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .ok_or_else(|| eyre!("Expected `key = value` at line {}", n + 1))?;
+            let invalid = || format!("Invalid value {value:?} for {key} at line {}", n + 1);
+            match key {
+                "rock" => rules.shapes[0] = value.parse().wrap_err_with(invalid)?,
+                "paper" => rules.shapes[1] = value.parse().wrap_err_with(invalid)?,
+                "scissors" => rules.shapes[2] = value.parse().wrap_err_with(invalid)?,
+                "win" => rules.win = value.parse().wrap_err_with(invalid)?,
+                "draw" => rules.draw = value.parse().wrap_err_with(invalid)?,
+                "loss" => rules.loss = value.parse().wrap_err_with(invalid)?,
+                "streak_length" => match value.parse().wrap_err_with(invalid)? {
+                    0 => return Err(eyre!("streak_length must be at least 1 at line {}", n + 1)),
+                    l => length = Some(l),
+                },
+                "streak_bonus" => bonus = Some(value.parse().wrap_err_with(invalid)?),
+                _ => return Err(eyre!("Unknown key {key:?} at line {}", n + 1)),
+            }
+        }
+
+        rules.streak = match (length, bonus) {
+            (Some(length), Some(bonus)) => Some(Streak { length, bonus }),
+            (None, None) => None,
+            _ => return Err(eyre!("Both streak_length and streak_bonus are needed")),
+        };
+        Ok(rules)
+    }
+}
+
+fn score(game: &[(Shape, Shape)], rules: &ScoringRules) -> Result<i32> {
+    let mut wins = 0;
+    game.iter().enumerate().try_fold(0i32, |total, (n, round)| {
+        wins = match outcome(round) {
+            Outcome::Win => wins + 1,
+            _ => 0,
+        };
+        rules
+            .round(round)?
+            .checked_add(rules.streak_bonus(wins))
+            .and_then(|points| total.checked_add(points))
+            .ok_or_else(|| eyre!("Total score overflows at round {}", n + 1))
+    })
+}
+
+/// Plays the highest scoring response against each of the opponent's shapes
+///
+/// Rounds are optimized independently, so streak bonuses are not considered.
+fn optimize(game: &[(Shape, Shape)], rules: &ScoringRules) -> Result<Vec<(Shape, Shape)>> {
     game.iter()
-        .map(|pair| match pair {
-            (Rock, Rock) => 1 + 3,
-            (Rock, Paper) => 2 + 6,
-            (Rock, Scissors) => 3,
-            (Paper, Rock) => 1,
-            (Paper, Paper) => 2 + 3,
-            (Paper, Scissors) => 3 + 6,
-            (Scissors, Rock) => 1 + 6,
-            (Scissors, Paper) => 2,
-            (Scissors, Scissors) => 3 + 3,
+        .map(|&(opponent, _)| {
+            let (_, best) = Shape::ALL
+                .into_iter()
+                .map(|me| Ok((rules.round(&(opponent, me))?, me)))
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .max_by_key(|&(points, _)| points)
+                .unwrap();
+            Ok((opponent, best))
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Analysis {
+    as_shapes: i32,
+    as_outcomes: i32,
+    optimal: i32,
+    /// Rounds played with the highest scoring response
+    best_responses: Vec<(Shape, Shape)>,
+}
+
+fn analyze(input: &str, columns: &Columns, rules: &ScoringRules) -> Result<Analysis> {
+    let game = parse(input, columns)?;
+    let as_outcomes = score(&to_game(&parse_outcome(input, columns)?), rules)?;
+
+    let best_responses = optimize(&game, rules)?;
+
+    Ok(Analysis {
+        as_shapes: score(&game, rules)?,
+        as_outcomes,
+        optimal: score(&best_responses, rules)?,
+        best_responses,
+    })
+}
+
+/// Relative frequencies of the opponent playing `Rock`, `Paper` and `Scissors`
+#[derive(Debug, Clone, PartialEq)]
+struct Opponent([f64; 3]);
+
+impl Default for Opponent {
+    fn default() -> Self {
+        Self([1.0; 3])
+    }
+}
+
+impl FromStr for Opponent {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let weights = s
+            .split(',')
+            .map(|w| {
+                w.trim()
+                    .parse()
+                    .wrap_err_with(|| format!("Invalid weight {w:?}"))
+            })
+            .collect::<Result<Vec<f64>>>()?;
+        Ok(Self(weights.try_into().map_err(|_| {
+            eyre!("Expected three weights for rock, paper and scissors, got {s:?}")
+        })?))
+    }
+}
+
+impl std::fmt::Display for Opponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [rock, paper, scissors] = self.0;
+        write!(f, "rock={rock} paper={paper} scissors={scissors}")
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Estimate {
+    mean: f64,
+    variance: f64,
+}
+
+/// Estimates the total score of playing `responses` against a random opponent
+fn simulate(
+    responses: &[Shape],
+    opponent: &Opponent,
+    rules: &ScoringRules,
+    trials: usize,
+    rng: &mut impl Rng,
+) -> Result<Estimate> {
+    if trials == 0 {
+        return Err(eyre!("At least one trial is needed"));
+    }
+    let shapes = WeightedIndex::new(opponent.0)
+        .wrap_err_with(|| format!("Invalid opponent distribution {opponent}"))?;
+
+    let totals = (0..trials)
+        .map(|_| {
+            let game: Vec<_> = responses
+                .iter()
+                .map(|&me| (Shape::ALL[shapes.sample(rng)], me))
+                .collect();
+            Ok(score(&game, rules)?.into())
         })
-        .sum()
+        .collect::<Result<Vec<f64>>>()?;
+
+    let mean = totals.iter().sum::<f64>() / trials as f64;
+    let variance = totals.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / trials as f64;
+    Ok(Estimate { mean, variance })
 }
 
 #[cfg(test)]
@@ -134,7 +484,7 @@ mod tests {
 
     #[rstest]
     fn test_first(input: &str) {
-        let game = parse(input);
+        let game = parse(input, &Columns::default()).unwrap();
 
         assert_eq!(
             game,
@@ -145,13 +495,13 @@ mod tests {
             ]
         );
 
-        let total = score(&game);
+        let total = score(&game, &ScoringRules::default()).unwrap();
         assert_eq!(total, 15);
     }
 
     #[rstest]
     fn test_second(input: &str) {
-        let result = parse_outcome(input);
+        let result = parse_outcome(input, &Columns::default()).unwrap();
 
         assert_eq!(
             result,
@@ -172,7 +522,174 @@ mod tests {
             ]
         );
 
-        let total = score(&game);
+        let total = score(&game, &ScoringRules::default()).unwrap();
         assert_eq!(total, 12);
     }
+
+    #[rstest]
+    fn test_custom_columns() {
+        let columns: Columns = "RPS,RPS".parse().unwrap();
+        let game = parse("R P\nP R\nS S\n", &columns).unwrap();
+
+        assert_eq!(score(&game, &ScoringRules::default()).unwrap(), 15);
+    }
+
+    #[rstest]
+    #[case("A Y\nB Q\n", "Invalid line 2", "Invalid symbol 'Q' at column 3")]
+    #[case("A Y\nD X\n", "Invalid line 2", "Invalid symbol 'D' at column 1")]
+    #[case("A\n", "Invalid line 1", "Missing symbol at column 3")]
+    #[case("A Y Z\n", "Invalid line 1", "Unexpected ' ' at column 4")]
+    #[case("A-Y\n", "Invalid line 1", "Expected space, got '-' at column 2")]
+    #[case("A Y\nB Q\n\n\n", "Invalid line 2", "Invalid symbol 'Q' at column 3")]
+    #[case("\nA Y\n", "Invalid line 1", "Missing symbol at column 1")]
+    #[case(" A Y\n", "Invalid line 1", "Invalid symbol ' ' at column 1")]
+    #[case("A Y\n\nB X\n", "Invalid line 2", "Missing symbol at column 1")]
+    fn test_invalid(#[case] input: &str, #[case] line: &str, #[case] cause: &str) {
+        let err = parse(input, &Columns::default()).unwrap_err();
+
+        assert_eq!(err.to_string(), line);
+        assert_eq!(err.root_cause().to_string(), cause);
+    }
+
+    #[rstest]
+    fn test_analyze(input: &str) {
+        let analysis = analyze(input, &Columns::default(), &ScoringRules::default()).unwrap();
+
+        assert_eq!(
+            analysis,
+            Analysis {
+                as_shapes: 15,
+                as_outcomes: 12,
+                optimal: 24,
+                best_responses: vec![
+                    (Shape::Rock, Shape::Paper),
+                    (Shape::Paper, Shape::Scissors),
+                    (Shape::Scissors, Shape::Rock),
+                ],
+            }
+        );
+    }
+
+    #[rstest]
+    fn test_optimize_custom_rules(input: &str) {
+        let rules: ScoringRules = "draw = 10\nrock = 10".parse().unwrap();
+        let game = parse(input, &Columns::default()).unwrap();
+
+        // Drawing beats winning, except when winning with rock scores 16
+        // instead of the 13 of a draw with scissors
+        assert_eq!(
+            optimize(&game, &rules).unwrap(),
+            vec![
+                (Shape::Rock, Shape::Rock),
+                (Shape::Paper, Shape::Paper),
+                (Shape::Scissors, Shape::Rock),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("1,0,0", 3.0 * 8.0)]
+    #[case("0,0,1", 3.0 * 2.0)]
+    fn test_simulate_fixed_opponent(#[case] opponent: &str, #[case] expected: f64) {
+        let responses = [Shape::Paper; 3];
+        let mut rng = StdRng::seed_from_u64(2022);
+        let estimate = simulate(
+            &responses,
+            &opponent.parse().unwrap(),
+            &ScoringRules::default(),
+            100,
+            &mut rng,
+        )
+        .unwrap();
+
+        assert_eq!(
+            estimate,
+            Estimate {
+                mean: expected,
+                variance: 0.0
+            }
+        );
+    }
+
+    #[rstest]
+    fn test_simulate_uniform_opponent() {
+        let responses = [Shape::Rock; 100];
+        let mut rng = StdRng::seed_from_u64(2022);
+        let estimate = simulate(
+            &responses,
+            &Opponent::default(),
+            &ScoringRules::default(),
+            1000,
+            &mut rng,
+        )
+        .unwrap();
+
+        // Each round scores 1 + 3, 1 + 0 or 1 + 6 with equal probability
+        assert!((estimate.mean - 400.0).abs() < 10.0);
+        assert!((estimate.variance - 600.0).abs() < 100.0);
+    }
+
+    #[rstest]
+    fn test_scoring_rules() {
+        let rules: ScoringRules = indoc! {"
+            # Tournament rules
+            rock = 10
+            paper = 20
+            scissors = 30
+            win = 100
+            draw = 50
+            loss = -10
+
+            streak_length = 2
+            streak_bonus = 1000
+        "}
+        .parse()
+        .unwrap();
+
+        let game = [
+            (Shape::Rock, Shape::Paper),
+            (Shape::Paper, Shape::Scissors),
+            (Shape::Scissors, Shape::Rock),
+            (Shape::Rock, Shape::Rock),
+            (Shape::Rock, Shape::Scissors),
+        ];
+        assert_eq!(
+            score(&game, &rules).unwrap(),
+            (20 + 100) + (30 + 100 + 1000) + (10 + 100 + 1000) + (10 + 50) + (30 - 10)
+        );
+    }
+
+    #[rstest]
+    #[case("win = 2147483647", "Score of (Rock, Paper) overflows")]
+    #[case("win = 2147483000", "Total score overflows at round 2")]
+    #[case(
+        "win = 2000000000\nstreak_length = 1\nstreak_bonus = 2000000000",
+        "Total score overflows at round 1"
+    )]
+    fn test_score_overflow(#[case] rules: &str, #[case] expected: &str) {
+        let rules: ScoringRules = rules.parse().unwrap();
+        let game = [
+            (Shape::Rock, Shape::Paper),
+            (Shape::Paper, Shape::Scissors),
+            (Shape::Scissors, Shape::Rock),
+        ];
+        let err = score(&game, &rules).unwrap_err();
+
+        assert_eq!(err.to_string(), expected);
+    }
+
+    #[rstest]
+    #[case("rock = one", "Invalid value \"one\" for rock at line 1")]
+    #[case("rock = 1\nlizard = 4", "Unknown key \"lizard\" at line 2")]
+    #[case("streak_bonus = 4", "Both streak_length and streak_bonus are needed")]
+    #[case("win 6", "Expected `key = value` at line 1")]
+    #[case(
+        "streak_length = 0\nstreak_bonus = 100",
+        "streak_length must be at least 1 at line 1"
+    )]
+    fn test_invalid_scoring_rules(#[case] input: &str, #[case] expected: &str) {
+        let err = input.parse::<ScoringRules>().unwrap_err();
+
+        assert_eq!(err.to_string(), expected);
+    }
 }
@@ -0,0 +1,72 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use day03::{sum_badges, sum_common, to_groups, to_sacks, Layout, Priority, PriorityTable};
+
+const ITEMS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Random rucksacks in groups of three sharing a badge
+fn manifest() -> String {
+    let mut rng = StdRng::seed_from_u64(2022);
+
+    let mut manifest = String::new();
+    for _ in 0..100_000 {
+        let badge = *ITEMS.choose(&mut rng).unwrap();
+        for len in [16, 24, 32] {
+            let mut sack: Vec<u8> = (0..len).map(|_| *ITEMS.choose(&mut rng).unwrap()).collect();
+            sack[rng.gen_range(0..len)] = badge;
+            manifest.extend(sack.into_iter().map(char::from));
+            manifest.push('\n');
+        }
+    }
+    manifest
+}
+
+fn common(c: &mut Criterion) {
+    let input = manifest();
+    let mut group = c.benchmark_group("common");
+
+    group.bench_function("hashset", |b| {
+        b.iter(|| {
+            let sacks = to_sacks(black_box(&input), &Layout::default()).unwrap();
+            Priority::total(sacks.iter().map(|s| s.common(&PriorityTable::default()))).unwrap()
+        })
+    });
+    group.bench_function("bitset", |b| {
+        b.iter(|| sum_common(black_box(input.as_bytes()), &Layout::default()).unwrap())
+    });
+    group.finish();
+}
+
+fn badges(c: &mut Criterion) {
+    let input = manifest();
+    let mut group = c.benchmark_group("badges");
+
+    group.bench_function("hashset", |b| {
+        b.iter_batched(
+            || to_sacks(&input, &Layout::default()).unwrap(),
+            |sacks| {
+                let groups = to_groups(
+                    black_box(&sacks),
+                    &Layout::default(),
+                    &PriorityTable::default(),
+                )
+                .unwrap();
+                Priority::total(
+                    groups
+                        .into_iter()
+                        .map(|p| Priority::total(p.into_iter().map(Ok))),
+                )
+                .unwrap()
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("bitset", |b| {
+        b.iter(|| sum_badges(black_box(input.as_bytes()), &Layout::default()).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, common, badges);
+criterion_main!(benches);
@@ -0,0 +1,922 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use color_eyre::{
+    eyre::{eyre, ContextCompat, WrapErr},
+    Report, Result,
+};
+use derive_more::{Display, From};
+use itertools::Itertools;
+use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How the items of a rucksack are split into compartments, and how
+/// rucksacks are grouped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    compartments: usize,
+    group_size: usize,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            compartments: 2,
+            group_size: 3,
+        }
+    }
+}
+
+impl Layout {
+    pub fn new(compartments: usize, group_size: usize) -> Result<Self> {
+        if compartments == 0 || group_size == 0 {
+            return Err(eyre!(
+                "Invalid layout of {compartments} compartments in groups of {group_size}"
+            ));
+        }
+        Ok(Self {
+            compartments,
+            group_size,
+        })
+    }
+
+    /// Returns the number of items in each compartment of a rucksack
+    fn compartment_size(&self, items: usize) -> Result<usize> {
+        if items == 0 {
+            return Err(eyre!("Empty rucksack"));
+        }
+        if !items.is_multiple_of(self.compartments) {
+            return Err(eyre!(
+                "{items} items cannot be split into {} compartments",
+                self.compartments
+            ));
+        }
+        Ok(items / self.compartments)
+    }
+
+    fn check_groups(&self, sacks: usize) -> Result<()> {
+        if !sacks.is_multiple_of(self.group_size) {
+            return Err(eyre!(
+                "{sacks} rucksacks cannot be split into groups of {}",
+                self.group_size
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Items in each compartment, where every item is a single grapheme cluster
+pub struct RuckSack {
+    compartments: Vec<HashSet<String>>,
+}
+
+#[repr(transparent)]
+#[derive(Display, Debug, Clone, Copy, PartialEq, From, Serialize)]
+pub struct Priority(u32);
+
+impl Priority {
+    pub fn checked_add(self, other: Self) -> Result<Self> {
+        self.0
+            .checked_add(other.0)
+            .map(Self)
+            .wrap_err("Sum of priorities overflows")
+    }
+
+    /// Adds up the priorities, failing on the first error or on overflow
+    pub fn total(priorities: impl IntoIterator<Item = Result<Self>>) -> Result<Self> {
+        priorities
+            .into_iter()
+            .try_fold(Self(0), |total, prio| total.checked_add(prio?))
+    }
+}
+
+/// Maps item types to their priority
+///
+/// Tables are read from lines of `<item> <priority>` pairs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriorityTable(HashMap<String, Priority>);
+
+impl Default for PriorityTable {
+    fn default() -> Self {
+        Self(
+            ('a'..='z')
+                .chain('A'..='Z')
+                .zip(1..)
+                .map(|(item, prio)| (item.to_string(), Priority(prio)))
+                .collect(),
+        )
+    }
+}
+
+impl PriorityTable {
+    pub fn load(path: &str) -> Result<Self> {
+        std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read from {path:?}"))?
+            .parse()
+            .wrap_err_with(|| format!("Invalid priority table in {path:?}"))
+    }
+
+    pub fn priority(&self, item: &str) -> Result<Priority> {
+        self.0
+            .get(item)
+            .copied()
+            .wrap_err_with(|| format!("Invalid item {item}"))
+    }
+}
+
+impl FromStr for PriorityTable {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = HashMap::new();
+        s.lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .map(|(n, l)| {
+                let (item, prio) = l
+                    .split_whitespace()
+                    .collect_tuple()
+                    .wrap_err_with(|| format!("Expected `<item> <priority>` at line {}", n + 1))?;
+                if item.graphemes(true).count() != 1 {
+                    return Err(eyre!(
+                        "Item {item:?} at line {} is not a single character",
+                        n + 1
+                    ));
+                }
+                let prio = prio
+                    .parse()
+                    .wrap_err_with(|| format!("Invalid priority {prio:?} at line {}", n + 1))?;
+                if let Some(first) = lines.insert(item, n + 1) {
+                    return Err(eyre!(
+                        "Item {item:?} at line {} is already listed at line {first}",
+                        n + 1
+                    ));
+                }
+                Ok((item.to_string(), Priority(prio)))
+            })
+            .collect::<Result<_>>()
+            .map(Self)
+    }
+}
+
+impl RuckSack {
+    pub fn parse(line: &str, layout: &Layout) -> Result<Self> {
+        let items: Vec<&str> = line.graphemes(true).collect();
+        let size = layout.compartment_size(items.len())?;
+        Ok(Self {
+            compartments: items
+                .chunks(size)
+                .map(|c| c.iter().map(|&i| i.into()).collect())
+                .collect(),
+        })
+    }
+
+    /// Items found in every compartment
+    pub fn duplicates(&self) -> HashSet<&str> {
+        let (first, rest) = self.compartments.split_first().unwrap();
+        first
+            .iter()
+            .map(String::as_str)
+            .filter(|item| rest.iter().all(|c| c.contains(*item)))
+            .collect()
+    }
+
+    pub fn common(&self, table: &PriorityTable) -> Result<Priority> {
+        Priority::total(
+            self.duplicates()
+                .into_iter()
+                .map(|item| table.priority(item)),
+        )
+    }
+
+    pub fn common_2(&self) -> HashSet<&str> {
+        self.compartments
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+pub fn to_sacks(input: &str, layout: &Layout) -> Result<Vec<RuckSack>> {
+    libaoc::lines(input)
+        .map(|(n, l)| {
+            RuckSack::parse(l, layout).wrap_err_with(|| format!("Invalid rucksack at line {n}"))
+        })
+        .collect()
+}
+
+/// Returns the items common to all rucksacks of each group
+pub fn badges<'a>(sacks: &'a [RuckSack], layout: &Layout) -> Result<Vec<HashSet<&'a str>>> {
+    layout.check_groups(sacks.len())?;
+    Ok(sacks.chunks(layout.group_size).map(group_items).collect())
+}
+
+/// Items found in every rucksack of a group
+fn group_items(group: &[RuckSack]) -> HashSet<&str> {
+    let init = group[0].common_2();
+    group[1..]
+        .iter()
+        .map(|s| s.common_2())
+        .fold(init, |acc, x| acc.intersection(&x).copied().collect())
+}
+
+pub fn to_groups(
+    sacks: &[RuckSack],
+    layout: &Layout,
+    table: &PriorityTable,
+) -> Result<Vec<Vec<Priority>>> {
+    badges(sacks, layout)?
+        .into_iter()
+        .map(|badge| badge.into_iter().map(|item| table.priority(item)).collect())
+        .collect()
+}
+
+impl FromStr for RuckSack {
+    type Err = Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::parse(value, &Layout::default())
+    }
+}
+
+/// Set of item types, stored as one bit per item priority
+///
+/// Only the ASCII letters of the default [`PriorityTable`] are supported.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Items(u64);
+
+impl Items {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        bytes.iter().try_fold(Self::default(), |acc, b| {
+            let bit = match b {
+                b'a'..=b'z' => b - b'a' + 1,
+                b'A'..=b'Z' => b - b'A' + 27,
+                _ => return Err(eyre!("Invalid item {}", char::from(*b))),
+            };
+            Ok(Self(acc.0 | 1 << bit))
+        })
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn priorities(self) -> impl Iterator<Item = Priority> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let prio = bits.trailing_zeros();
+            bits &= bits - 1;
+            Some(Priority(prio))
+        })
+    }
+
+    pub fn priority(self) -> Priority {
+        // At most 64 bits are set, so this cannot overflow
+        Priority(self.priorities().map(|Priority(p)| p).sum())
+    }
+
+    pub fn items(self) -> impl Iterator<Item = char> {
+        self.priorities().map(|Priority(p)| match p {
+            1..=26 => char::from(b'a' + p as u8 - 1),
+            _ => char::from(b'A' + p as u8 - 27),
+        })
+    }
+}
+
+/// Allocation free counterpart of [`RuckSack`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitSack {
+    common: Items,
+    all: Items,
+}
+
+impl BitSack {
+    pub fn parse(line: &[u8], layout: &Layout) -> Result<Self> {
+        if !line.is_ascii() {
+            let line = String::from_utf8_lossy(line);
+            let item = line
+                .graphemes(true)
+                .find(|g| !g.is_ascii())
+                .unwrap_or_default();
+            return Err(eyre!("Invalid item {item}"));
+        }
+        let size = layout.compartment_size(line.len())?;
+        let (common, all) = line.chunks(size).try_fold(
+            (Items(u64::MAX), Items::default()),
+            |(common, all), compartment| {
+                let items = Items::from_bytes(compartment)?;
+                Ok::<_, Report>((common.intersection(items), all.union(items)))
+            },
+        )?;
+        Ok(Self { common, all })
+    }
+
+    /// Items found in every compartment
+    pub fn common(self) -> Items {
+        self.common
+    }
+
+    pub fn all(self) -> Items {
+        self.all
+    }
+}
+
+/// Lines numbered from 1, without the blank lines at the end of the input,
+/// like [`libaoc::lines`]
+fn byte_lines(input: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    let lines = || {
+        input
+            .split(|b| *b == b'\n')
+            .map(|l| l.strip_suffix(b"\r").unwrap_or(l))
+    };
+    let end = lines()
+        .enumerate()
+        .filter(|(_, l)| !l.iter().all(u8::is_ascii_whitespace))
+        .last()
+        .map_or(0, |(n, _)| n + 1);
+    lines().take(end).zip(1..).map(|(l, n)| (n, l))
+}
+
+fn parse_bitsacks<'a>(
+    input: &'a [u8],
+    layout: &'a Layout,
+) -> impl Iterator<Item = Result<BitSack>> + 'a {
+    byte_lines(input).map(|(n, l)| {
+        BitSack::parse(l, layout).wrap_err_with(|| format!("Invalid rucksack at line {n}"))
+    })
+}
+
+pub fn sum_common(input: &[u8], layout: &Layout) -> Result<Priority> {
+    Priority::total(parse_bitsacks(input, layout).map(|s| Ok(s?.common().priority())))
+}
+
+/// Calls `f` with the badge candidates of each group of rucksacks
+fn for_each_group(
+    input: &[u8],
+    layout: &Layout,
+    mut f: impl FnMut(Items) -> Result<()>,
+) -> Result<()> {
+    let mut badge = Items(u64::MAX);
+    let mut members = 0;
+    let mut sacks = 0;
+
+    for sack in parse_bitsacks(input, layout) {
+        badge = badge.intersection(sack?.all());
+        members += 1;
+        sacks += 1;
+        if members == layout.group_size {
+            f(badge)?;
+            badge = Items(u64::MAX);
+            members = 0;
+        }
+    }
+
+    layout.check_groups(sacks)
+}
+
+pub fn sum_badges(input: &[u8], layout: &Layout) -> Result<Priority> {
+    let mut total = Priority(0);
+    for_each_group(input, layout, |badge| {
+        total = total.checked_add(badge.priority())?;
+        Ok(())
+    })?;
+    Ok(total)
+}
+
+/// Returns the items common to all rucksacks of each group
+///
+/// A well packed group has exactly one such item, its badge.
+pub fn group_badges(input: &[u8], layout: &Layout) -> Result<Vec<Items>> {
+    let mut badges = vec![];
+    for_each_group(input, layout, |badge| {
+        badges.push(badge);
+        Ok(())
+    })?;
+    Ok(badges)
+}
+
+/// Whether a rucksack or group has exactly one common item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Ok,
+    Missing,
+    Multiple,
+    /// The last group has fewer rucksacks than the group size
+    Incomplete,
+}
+
+impl Status {
+    fn of(items: &[ItemAudit]) -> Self {
+        match items.len() {
+            0 => Self::Missing,
+            1 => Self::Ok,
+            _ => Self::Multiple,
+        }
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ok => "ok",
+            Self::Missing => "missing",
+            Self::Multiple => "multiple",
+            Self::Incomplete => "incomplete",
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ItemAudit {
+    pub item: String,
+    pub priority: Priority,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SackAudit {
+    pub line: usize,
+    pub duplicates: Vec<ItemAudit>,
+    pub status: Status,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct GroupAudit {
+    pub group: usize,
+    pub badges: Vec<ItemAudit>,
+    pub status: Status,
+}
+
+/// Packing audit of every rucksack and group
+///
+/// A last group with too few rucksacks is reported as incomplete, without
+/// badges. Displayed as a plain text table, or serialized to JSON.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Audit {
+    pub sacks: Vec<SackAudit>,
+    pub groups: Vec<GroupAudit>,
+}
+
+fn audit_items<'a>(
+    items: impl IntoIterator<Item = &'a str>,
+    table: &PriorityTable,
+) -> Result<Vec<ItemAudit>> {
+    items
+        .into_iter()
+        .sorted()
+        .map(|item| {
+            Ok(ItemAudit {
+                item: item.into(),
+                priority: table.priority(item)?,
+            })
+        })
+        .collect()
+}
+
+pub fn audit(sacks: &[RuckSack], layout: &Layout, table: &PriorityTable) -> Result<Audit> {
+    let sack_audits = sacks
+        .iter()
+        .enumerate()
+        .map(|(n, sack)| {
+            let duplicates = audit_items(sack.duplicates(), table)
+                .wrap_err_with(|| format!("Invalid rucksack at line {}", n + 1))?;
+            Ok(SackAudit {
+                line: n + 1,
+                status: Status::of(&duplicates),
+                duplicates,
+            })
+        })
+        .collect::<Result<_>>()?;
+    let groups = sacks
+        .chunks(layout.group_size)
+        .enumerate()
+        .map(|(n, group)| {
+            if group.len() < layout.group_size {
+                return Ok(GroupAudit {
+                    group: n + 1,
+                    badges: vec![],
+                    status: Status::Incomplete,
+                });
+            }
+            let badges = audit_items(group_items(group), table)
+                .wrap_err_with(|| format!("Invalid group {}", n + 1))?;
+            Ok(GroupAudit {
+                group: n + 1,
+                status: Status::of(&badges),
+                badges,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Audit {
+        sacks: sack_audits,
+        groups,
+    })
+}
+
+impl Display for Audit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn columns(items: &[ItemAudit]) -> (String, String) {
+            (
+                items.iter().map(|i| &i.item).join(" "),
+                items.iter().map(|i| i.priority).join(" "),
+            )
+        }
+
+        writeln!(
+            f,
+            "{:>6}  {:<12}  {:<12}  Status",
+            "Line", "Duplicates", "Priorities"
+        )?;
+        for sack in &self.sacks {
+            let (items, priorities) = columns(&sack.duplicates);
+            writeln!(
+                f,
+                "{:>6}  {items:<12}  {priorities:<12}  {}",
+                sack.line, sack.status
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:>6}  {:<12}  {:<12}  Status",
+            "Group", "Badges", "Priorities"
+        )?;
+        for group in &self.groups {
+            let (items, priorities) = columns(&group.badges);
+            writeln!(
+                f,
+                "{:>6}  {items:<12}  {priorities:<12}  {}",
+                group.group, group.status
+            )?;
+        }
+        writeln!(f)?;
+
+        writeln!(
+            f,
+            "{} of {} rucksacks and {} of {} groups need attention",
+            self.sacks.iter().filter(|s| s.status != Status::Ok).count(),
+            self.sacks.len(),
+            self.groups
+                .iter()
+                .filter(|g| g.status != Status::Ok)
+                .count(),
+            self.groups.len(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    use super::*;
+
+    #[fixture]
+    fn input() -> &'static str {
+        indoc! {"
+            vJrwpWtwJgWrhcsFMMfFFhFp
+            jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+            PmmdzqPrVvPwwTWBwg
+            wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+            ttgJtRGJQctTZtZT
+            CrZsJsPPZsGzwwsLwLmpwMDw
+        "}
+    }
+
+    #[rstest]
+    fn test_first(input: &str) {
+        let sacks = to_sacks(input, &Layout::default()).unwrap();
+        let prio =
+            Priority::total(sacks.iter().map(|s| s.common(&PriorityTable::default()))).unwrap();
+
+        assert_eq!(prio, 157.into());
+    }
+    #[rstest]
+    fn test_second(input: &str) {
+        let sacks = to_sacks(input, &Layout::default()).unwrap();
+        let groups = to_groups(&sacks, &Layout::default(), &PriorityTable::default()).unwrap();
+
+        let sum = Priority::total(
+            groups
+                .into_iter()
+                .map(|p| Priority::total(p.into_iter().map(Ok))),
+        )
+        .unwrap();
+
+        assert_eq!(sum, 70.into());
+    }
+
+    #[rstest]
+    fn test_bitset(input: &str) {
+        let layout = Layout::default();
+
+        assert_eq!(sum_common(input.as_bytes(), &layout).unwrap(), 157.into());
+        assert_eq!(sum_badges(input.as_bytes(), &layout).unwrap(), 70.into());
+    }
+
+    #[rstest]
+    #[case(b"vJrwpWtwJgWrhcsFMMfFFhFp", &[16])]
+    #[case(b"abcdabcd", &[1, 2, 3, 4])]
+    #[case(b"abcdefgh", &[])]
+    fn test_common_items(#[case] line: &[u8], #[case] expected: &[u32]) {
+        let common = BitSack::parse(line, &Layout::default()).unwrap().common();
+
+        assert_eq!(common.len(), expected.len());
+        assert_eq!(
+            common.priorities().collect::<Vec<_>>(),
+            expected.iter().map(|&p| Priority(p)).collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    fn test_bitset_invalid_item() {
+        let err = sum_common(b"abcd\nab1d", &Layout::default()).unwrap_err();
+
+        assert_eq!(err.to_string(), "Invalid rucksack at line 2");
+        assert_eq!(err.root_cause().to_string(), "Invalid item 1");
+    }
+
+    #[rstest]
+    fn test_bitset_non_ascii_item() {
+        let err =
+            sum_common("abab\nae\u{301}ae\u{301}".as_bytes(), &Layout::default()).unwrap_err();
+
+        assert_eq!(err.to_string(), "Invalid rucksack at line 2");
+        assert_eq!(err.root_cause().to_string(), "Invalid item e\u{301}");
+    }
+
+    #[rstest]
+    fn test_layout() {
+        let input = indoc! {"
+            bcXbcYbcZ
+            efXefYefZ
+            axyzAxyzBxyz
+            bxyzCxyzDxyz
+        "};
+        let layout = Layout::new(3, 2).unwrap();
+
+        let sacks = to_sacks(input, &layout).unwrap();
+        let common =
+            Priority::total(sacks.iter().map(|s| s.common(&PriorityTable::default()))).unwrap();
+        assert_eq!(common, (2 + 3 + 5 + 6 + 2 * (24 + 25 + 26)).into());
+        assert_eq!(sum_common(input.as_bytes(), &layout).unwrap(), common);
+
+        let badges = group_badges(input.as_bytes(), &layout).unwrap();
+        assert_eq!(
+            badges
+                .iter()
+                .map(|b| b.items().collect::<String>())
+                .collect::<Vec<_>>(),
+            vec!["XYZ".to_string(), "xyz".to_string()]
+        );
+    }
+
+    #[rstest]
+    fn test_odd_rucksack() {
+        let input = "abab\nabcab\nabab\n";
+        let layout = Layout::default();
+
+        for err in [
+            sum_common(input.as_bytes(), &layout).unwrap_err(),
+            to_sacks(input, &layout).err().unwrap(),
+        ] {
+            assert_eq!(err.to_string(), "Invalid rucksack at line 2");
+            assert_eq!(
+                err.root_cause().to_string(),
+                "5 items cannot be split into 2 compartments"
+            );
+        }
+    }
+
+    #[rstest]
+    #[case("abab\n\nabab\nabab\n", "Invalid rucksack at line 2", "Empty rucksack")]
+    #[case("\nabab\nabab\nabab\n", "Invalid rucksack at line 1", "Empty rucksack")]
+    #[case("abab\n\n\nabc\n", "Invalid rucksack at line 2", "Empty rucksack")]
+    #[case(
+        "abab\r\nabab\r\nabc\r\n",
+        "Invalid rucksack at line 3",
+        "3 items cannot be split into 2 compartments"
+    )]
+    fn test_blank_lines(#[case] input: &str, #[case] error: &str, #[case] cause: &str) {
+        let layout = Layout::default();
+        let table = PriorityTable::default();
+        let hashset = to_sacks(input, &layout)
+            .and_then(|sacks| Priority::total(sacks.iter().map(|s| s.common(&table))));
+
+        for err in [
+            hashset.unwrap_err(),
+            sum_common(input.as_bytes(), &layout).unwrap_err(),
+        ] {
+            assert_eq!(err.to_string(), error);
+            assert_eq!(err.root_cause().to_string(), cause);
+        }
+    }
+
+    #[rstest]
+    fn test_trailing_blank_lines(input: &str) {
+        let input = format!("{input}\n  \n\r\n");
+        let layout = Layout::default();
+        let sacks = to_sacks(&input, &layout).unwrap();
+
+        assert_eq!(sacks.len(), 6);
+        assert_eq!(sum_common(input.as_bytes(), &layout).unwrap(), 157.into());
+        assert_eq!(sum_badges(input.as_bytes(), &layout).unwrap(), 70.into());
+    }
+
+    #[rstest]
+    fn test_incomplete_group() {
+        let input = "abab\nabab\nabab\nabab\n";
+        let layout = Layout::default();
+        let sacks = to_sacks(input, &layout).unwrap();
+
+        for err in [
+            sum_badges(input.as_bytes(), &layout).unwrap_err(),
+            to_groups(&sacks, &layout, &PriorityTable::default()).unwrap_err(),
+        ] {
+            assert_eq!(
+                err.to_string(),
+                "4 rucksacks cannot be split into groups of 3"
+            );
+        }
+    }
+
+    #[rstest]
+    fn test_default_priorities() {
+        let table = PriorityTable::default();
+
+        assert_eq!(table.priority("a").unwrap(), 1.into());
+        assert_eq!(table.priority("z").unwrap(), 26.into());
+        assert_eq!(table.priority("A").unwrap(), 27.into());
+        assert_eq!(table.priority("Z").unwrap(), 52.into());
+        assert!(table.priority("é").is_err());
+    }
+
+    #[rstest]
+    fn test_unicode_items() {
+        let table: PriorityTable = indoc! {"
+            e\u{301} 10
+            ß 20
+            x 1
+            y 2
+            z 3
+        "}
+        .parse()
+        .unwrap();
+        let input = "e\u{301}xße\u{301}yz\nßßyxxß\nzzzßzz\n";
+
+        let sacks = to_sacks(input, &Layout::default()).unwrap();
+        let common = Priority::total(sacks.iter().map(|s| s.common(&table))).unwrap();
+        assert_eq!(common, (10 + 20 + 3).into());
+
+        let groups = to_groups(&sacks, &Layout::default(), &table).unwrap();
+        assert_eq!(groups, vec![vec![Priority(20)]]);
+    }
+
+    #[rstest]
+    #[case("a", "Expected `<item> <priority>` at line 1")]
+    #[case("a 1\nab 2", "Item \"ab\" at line 2 is not a single character")]
+    #[case("a one", "Invalid priority \"one\" at line 1")]
+    #[case("a 1\nb 2\n\na 3", "Item \"a\" at line 4 is already listed at line 1")]
+    fn test_invalid_priority_table(#[case] input: &str, #[case] expected: &str) {
+        let err = input.parse::<PriorityTable>().unwrap_err();
+
+        assert_eq!(err.to_string(), expected);
+    }
+
+    #[rstest]
+    fn test_priority_overflow() {
+        let table: PriorityTable = "a 4294967295\nb 1\nc 1".parse().unwrap();
+        let layout = Layout::default();
+
+        let sacks = to_sacks("abab\nacac\nacac\n", &layout).unwrap();
+        let err = sacks[0].common(&table).unwrap_err();
+        assert_eq!(err.to_string(), "Sum of priorities overflows");
+
+        let sacks = to_sacks("aa\naa\naa\n", &layout).unwrap();
+        let err = Priority::total(sacks.iter().map(|s| s.common(&table))).unwrap_err();
+        assert_eq!(err.to_string(), "Sum of priorities overflows");
+
+        let groups = to_groups(&sacks, &layout, &table).unwrap();
+        assert_eq!(groups, vec![vec![Priority(u32::MAX)]]);
+    }
+
+    #[rstest]
+    fn test_audit() {
+        let input = indoc! {"
+            abcxyzabcxyz
+            aabbccaabbdd
+            abcdefghijkl
+        "};
+        let layout = Layout::default();
+        let sacks = to_sacks(input, &layout).unwrap();
+        let audit = audit(&sacks, &layout, &PriorityTable::default()).unwrap();
+
+        assert_eq!(
+            audit
+                .sacks
+                .iter()
+                .map(|s| (s.line, s.duplicates.len(), s.status))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, 6, Status::Multiple),
+                (2, 2, Status::Multiple),
+                (3, 0, Status::Missing),
+            ]
+        );
+        assert_eq!(
+            audit.groups,
+            vec![GroupAudit {
+                group: 1,
+                badges: vec![
+                    ItemAudit {
+                        item: "a".into(),
+                        priority: 1.into()
+                    },
+                    ItemAudit {
+                        item: "b".into(),
+                        priority: 2.into()
+                    },
+                    ItemAudit {
+                        item: "c".into(),
+                        priority: 3.into()
+                    },
+                ],
+                status: Status::Multiple,
+            }]
+        );
+    }
+
+    #[rstest]
+    fn test_audit_incomplete_group(input: &str) {
+        let layout = Layout::default();
+        let sacks = to_sacks(input, &layout).unwrap();
+        let audit = audit(&sacks[..5], &layout, &PriorityTable::default()).unwrap();
+
+        assert_eq!(audit.sacks.len(), 5);
+        assert!(audit.sacks.iter().all(|s| s.status == Status::Ok));
+        assert_eq!(
+            audit
+                .groups
+                .iter()
+                .map(|g| (g.group, g.status))
+                .collect::<Vec<_>>(),
+            vec![(1, Status::Ok), (2, Status::Incomplete)]
+        );
+        assert!(audit.groups[1].badges.is_empty());
+        assert!(audit
+            .to_string()
+            .ends_with("0 of 5 rucksacks and 1 of 2 groups need attention\n"));
+    }
+
+    #[rstest]
+    fn test_audit_format(input: &str) {
+        let layout = Layout::default();
+        let sacks = to_sacks(input, &layout).unwrap();
+        let audit = audit(&sacks[..3], &layout, &PriorityTable::default()).unwrap();
+
+        assert_eq!(
+            audit.to_string(),
+            indoc! {"
+                  Line  Duplicates    Priorities    Status
+                     1  p             16            ok
+                     2  L             38            ok
+                     3  P             42            ok
+
+                 Group  Badges        Priorities    Status
+                     1  r             18            ok
+
+                0 of 3 rucksacks and 0 of 1 groups need attention
+            "}
+        );
+        assert_eq!(
+            serde_json::to_value(&audit.groups).unwrap(),
+            serde_json::json!([
+                {"group": 1, "badges": [{"item": "r", "priority": 18}], "status": "ok"}
+            ])
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use std::{cmp::Reverse, collections::BinaryHeap, ops::RangeInclusive};
+
+/// Set of sections, stored as sorted, disjoint and non-adjacent ranges
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IntervalSet(Vec<RangeInclusive<usize>>);
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut ranges: Vec<_> = self.0.iter().chain(other.0.iter()).cloned().collect();
+        ranges.sort_by_key(|r| *r.start());
+
+        let mut merged: Vec<RangeInclusive<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if *range.start() <= last.end().saturating_add(1) => {
+                    *last = *last.start()..=*last.end().max(range.end());
+                }
+                _ => merged.push(range),
+            }
+        }
+        Self(merged)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut result = vec![];
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.0.get(i), other.0.get(j)) {
+            let start = *a.start().max(b.start());
+            let end = *a.end().min(b.end());
+            if start <= end {
+                result.push(start..=end);
+            }
+            if a.end() < b.end() {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self(result)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut result = vec![];
+        let mut holes = other.0.iter().peekable();
+        'ranges: for range in &self.0 {
+            let mut start = *range.start();
+            let end = *range.end();
+            while let Some(hole) = holes.peek() {
+                if hole.end() < &start {
+                    holes.next();
+                    continue;
+                }
+                if *hole.start() > end {
+                    break;
+                }
+                if *hole.start() > start {
+                    result.push(start..=hole.start() - 1);
+                }
+                if *hole.end() >= end {
+                    continue 'ranges;
+                }
+                start = hole.end() + 1;
+                holes.next();
+            }
+            if start <= end {
+                result.push(start..=end);
+            }
+        }
+        Self(result)
+    }
+
+    /// Number of sections covered, saturating at `usize::MAX`
+    pub fn len(&self) -> usize {
+        self.0.iter().fold(0, |acc, r| {
+            acc.saturating_add((r.end() - r.start()).saturating_add(1))
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.difference(self).is_empty()
+    }
+
+    pub fn ranges(&self) -> impl Iterator<Item = &RangeInclusive<usize>> {
+        self.0.iter()
+    }
+}
+
+impl From<RangeInclusive<usize>> for IntervalSet {
+    fn from(range: RangeInclusive<usize>) -> Self {
+        if range.is_empty() {
+            Self::default()
+        } else {
+            Self(vec![range])
+        }
+    }
+}
+
+impl FromIterator<RangeInclusive<usize>> for IntervalSet {
+    fn from_iter<T: IntoIterator<Item = RangeInclusive<usize>>>(iter: T) -> Self {
+        Self(iter.into_iter().filter(|r| !r.is_empty()).collect()).union(&Self::default())
+    }
+}
+
+/// Returns the index pairs of all overlapping ranges
+///
+/// Ranges are swept in the order of their start, keeping the ones still open
+/// in a heap ordered by their end, so this takes `O(n log n + k)` time for
+/// `k` overlapping pairs.
+pub fn overlapping_pairs(ranges: &[RangeInclusive<usize>]) -> Vec<(usize, usize)> {
+    let mut order: Vec<_> = (0..ranges.len())
+        .filter(|&i| !ranges[i].is_empty())
+        .collect();
+    order.sort_by_key(|&i| *ranges[i].start());
+
+    let mut open = BinaryHeap::new();
+    let mut pairs = vec![];
+    for i in order {
+        let start = *ranges[i].start();
+        while let Some(&Reverse((end, _))) = open.peek() {
+            if end >= start {
+                break;
+            }
+            open.pop();
+        }
+        pairs.extend(open.iter().map(|&Reverse((_, j))| (i.min(j), i.max(j))));
+        open.push(Reverse((*ranges[i].end(), i)));
+    }
+    pairs.sort_unstable();
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    use super::*;
+
+    fn set(ranges: &[RangeInclusive<usize>]) -> IntervalSet {
+        ranges.iter().cloned().collect()
+    }
+
+    #[rstest]
+    #[case(&[1..=3, 5..=7], &[1..=3, 5..=7])]
+    #[case(&[5..=7, 1..=3], &[1..=3, 5..=7])]
+    #[case(&[1..=3, 4..=7], &[1..=7])]
+    #[case(&[1..=5, 2..=3], &[1..=5])]
+    #[case(&[RangeInclusive::new(3, 1)], &[])]
+    fn test_normalize(
+        #[case] ranges: &[RangeInclusive<usize>],
+        #[case] expected: &[RangeInclusive<usize>],
+    ) {
+        assert_eq!(set(ranges).0, expected);
+    }
+
+    #[rstest]
+    fn test_operations() {
+        let a = set(&[1..=5, 10..=15]);
+        let b = set(&[4..=11, 14..=20]);
+
+        assert_eq!(a.union(&b), set(&[1..=20]));
+        assert_eq!(a.intersection(&b), set(&[4..=5, 10..=11, 14..=15]));
+        assert_eq!(a.difference(&b), set(&[1..=3, 12..=13]));
+        assert_eq!(b.difference(&a), set(&[6..=9, 16..=20]));
+        assert_eq!(a.len(), 11);
+        assert!(a.union(&b).is_superset(&a));
+        assert!(!a.is_superset(&b));
+    }
+
+    #[rstest]
+    fn test_max_section() {
+        let a = set(&[5..=usize::MAX]);
+        let b = set(&[0..=usize::MAX]);
+
+        assert_eq!(b.difference(&a), set(&[0..=4]));
+        assert_eq!(a.difference(&b), set(&[]));
+        assert_eq!(a.difference(&set(&[7..=9])), set(&[5..=6, 10..=usize::MAX]));
+        assert_eq!(a.len(), usize::MAX - 4);
+        assert_eq!(b.len(), usize::MAX);
+        assert!(b.is_superset(&a));
+    }
+
+    #[rstest]
+    fn test_overlapping_pairs() {
+        let ranges = [2..=4, 6..=8, 3..=6, 9..=9, 1..=10];
+
+        assert_eq!(
+            overlapping_pairs(&ranges),
+            vec![(0, 2), (0, 4), (1, 2), (1, 4), (2, 4), (3, 4)]
+        );
+    }
+}
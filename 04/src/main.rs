@@ -1,61 +1,250 @@
-use std::{ops::RangeInclusive, str::FromStr};
+use std::{
+    fmt::{self, Display},
+    ops::RangeInclusive,
+    str::FromStr,
+};
 
-use color_eyre::{eyre::ContextCompat, Report, Result};
+use color_eyre::{
+    eyre::{eyre, ContextCompat, WrapErr},
+    Report, Result,
+};
 use once_cell::sync::Lazy;
 use regex::Regex;
 
+use intervals::{overlapping_pairs, IntervalSet};
+
+mod intervals;
+
 fn main() -> Result<()> {
     let input = libaoc::init()?;
+    let options = libaoc::options();
 
-    let a: Assignments = input.parse()?;
+    let reversed = options.parse("--reversed")?.unwrap_or_default();
+    let a = Assignments::parse(&input, reversed)?;
 
     let count = a.count_containment();
     println!("There are {count} pairs fully containing the other");
     let count = a.count_overlaps();
     println!("There are {count} pairs overlapping the other");
 
+    if options.flag("--report") {
+        for (n, crew) in a.0.iter().enumerate() {
+            let overlaps = crew.overlap_details();
+            if overlaps.is_empty() {
+                println!("Crew {}: no overlap", n + 1);
+            }
+            for overlap in overlaps {
+                println!("Crew {}: {overlap}", n + 1);
+            }
+        }
+
+        let stats = a.statistics();
+        println!(
+            "{} of {} crews overlap, {} fully containing another assignment",
+            stats.overlapping, stats.crews, stats.containing
+        );
+        println!(
+            "{} sections are assigned more than once, at most {} in a single crew",
+            stats.wasted, stats.max_wasted
+        );
+    }
+
+    if options.flag("--crews") {
+        for (n, crew) in a.0.iter().enumerate() {
+            let coverage = crew.coverage();
+            let shared = crew.shared();
+            let overlaps = crew
+                .overlaps()
+                .iter()
+                .map(|(i, j)| format!("{}-{}", i + 1, j + 1))
+                .collect::<Vec<_>>();
+            println!(
+                "Crew {}: {} elves cover {} sections ({} shared, {} exclusive), overlapping elves: [{}]",
+                n + 1,
+                crew.ranges.len(),
+                coverage.len(),
+                shared.len(),
+                coverage.difference(&shared).len(),
+                overlaps.join(", ")
+            );
+        }
+        let coverage = a.coverage();
+        let ranges = coverage
+            .ranges()
+            .map(|r| format!("{}-{}", r.start(), r.end()))
+            .collect::<Vec<_>>();
+        println!(
+            "All crews cover {} sections: {}",
+            coverage.len(),
+            ranges.join(",")
+        );
+    }
+
     Ok(())
 }
 
+/// Section ranges assigned to each elf of a crew
 struct Assignment {
-    first: RangeInclusive<usize>,
-    second: RangeInclusive<usize>,
+    ranges: Vec<RangeInclusive<usize>>,
 }
 
 impl Assignment {
     fn contains_other(&self) -> bool {
-        self.first.contains(self.second.start()) && self.first.contains(self.second.end())
-            || self.second.contains(self.first.start()) && self.second.contains(self.first.end())
+        self.overlap_details()
+            .iter()
+            .any(|o| o.containment != Containment::Partial)
     }
 
     fn overlaps_other(&self) -> bool {
-        self.first.contains(self.second.start())
-            || self.first.contains(self.second.end())
-            || self.second.contains(self.first.start())
-            || self.second.contains(self.first.end())
+        !self.overlaps().is_empty()
+    }
+
+    /// Index pairs of the elves with overlapping assignments
+    fn overlaps(&self) -> Vec<(usize, usize)> {
+        overlapping_pairs(&self.ranges)
+    }
+
+    /// Shared sections and containment of each pair of overlapping elves
+    fn overlap_details(&self) -> Vec<Overlap> {
+        self.overlaps()
+            .into_iter()
+            .map(|(i, j)| {
+                let (a, b) = (&self.ranges[i], &self.ranges[j]);
+                let (a_set, b_set) = (IntervalSet::from(a.clone()), IntervalSet::from(b.clone()));
+                let a_contains_b = a_set.is_superset(&b_set);
+                let b_contains_a = b_set.is_superset(&a_set);
+                Overlap {
+                    elves: (i, j),
+                    shared: *a.start().max(b.start())..=*a.end().min(b.end()),
+                    containment: match (a_contains_b, b_contains_a) {
+                        (true, true) => Containment::Equal,
+                        (true, false) => Containment::FirstContainsSecond,
+                        (false, true) => Containment::SecondContainsFirst,
+                        (false, false) => Containment::Partial,
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// Number of section assignments beyond the first for each section
+    fn wasted(&self) -> usize {
+        let mut seen = IntervalSet::new();
+        self.ranges.iter().fold(0, |wasted, r| {
+            let range = IntervalSet::from(r.clone());
+            let repeated = range.intersection(&seen).len();
+            seen = seen.union(&range);
+            wasted.saturating_add(repeated)
+        })
+    }
+
+    fn coverage(&self) -> IntervalSet {
+        self.ranges.iter().cloned().collect()
+    }
+
+    /// Sections assigned to more than one elf
+    fn shared(&self) -> IntervalSet {
+        self.overlaps()
+            .into_iter()
+            .fold(IntervalSet::new(), |acc, (i, j)| {
+                let a = IntervalSet::from(self.ranges[i].clone());
+                acc.union(&a.intersection(&self.ranges[j].clone().into()))
+            })
     }
 }
 
-static PATTERN: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(?P<first_start>\d+)-(?P<first_end>\d+),(?P<second_start>\d+)-(?P<second_end>\d+)")
-        .unwrap()
-});
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Containment {
+    Partial,
+    FirstContainsSecond,
+    SecondContainsFirst,
+    Equal,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Overlap {
+    elves: (usize, usize),
+    shared: RangeInclusive<usize>,
+    containment: Containment,
+}
+
+impl Overlap {
+    /// Number of sections both elves were assigned to
+    fn wasted(&self) -> usize {
+        (self.shared.end() - self.shared.start()).saturating_add(1)
+    }
+}
+
+impl Display for Overlap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (first, second) = (self.elves.0 + 1, self.elves.1 + 1);
+        match self.containment {
+            Containment::Partial => write!(f, "elves {first} and {second} overlap")?,
+            Containment::FirstContainsSecond => write!(f, "elf {first} contains elf {second}")?,
+            Containment::SecondContainsFirst => write!(f, "elf {second} contains elf {first}")?,
+            Containment::Equal => write!(f, "elves {first} and {second} are identical")?,
+        }
+        write!(
+            f,
+            " sharing {}-{} ({} sections)",
+            self.shared.start(),
+            self.shared.end(),
+            self.wasted()
+        )
+    }
+}
+
+static PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(?P<start>\d+)-(?P<end>\d+)$").unwrap());
+
+/// How to treat ranges whose start is after their end, like `5-2`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ReversedRanges {
+    #[default]
+    Reject,
+    Swap,
+}
+
+impl FromStr for ReversedRanges {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(Self::Reject),
+            "swap" => Ok(Self::Swap),
+            _ => Err(eyre!("Expected `reject` or `swap`, got {s:?}")),
+        }
+    }
+}
+
+impl Assignment {
+    fn parse(s: &str, reversed: ReversedRanges) -> Result<Self> {
+        let ranges = s
+            .split(',')
+            .map(|r| {
+                let c = PATTERN
+                    .captures(r)
+                    .wrap_err_with(|| format!("Invalid range {r:?}"))?;
+                let section = |name| -> Result<usize> {
+                    let n = c.name(name).unwrap().as_str();
+                    n.parse().wrap_err_with(|| format!("Invalid section {n:?}"))
+                };
+                let (start, end) = (section("start")?, section("end")?);
+                match reversed {
+                    _ if start <= end => Ok(start..=end),
+                    ReversedRanges::Swap => Ok(end..=start),
+                    ReversedRanges::Reject => Err(eyre!("Reversed range {r:?}")),
+                }
+            })
+            .collect::<Result<_>>()?;
+        Ok(Assignment { ranges })
+    }
+}
 
 impl FromStr for Assignment {
     type Err = Report;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let c = PATTERN
-            .captures(s)
-            .wrap_err_with(|| format!("Invalid line {s:?}"))?;
-        let first_start = c.name("first_start").unwrap().as_str().parse()?;
-        let first_end = c.name("first_end").unwrap().as_str().parse()?;
-        let second_start = c.name("second_start").unwrap().as_str().parse()?;
-        let second_end = c.name("second_end").unwrap().as_str().parse()?;
-        Ok(Assignment {
-            first: first_start..=first_end,
-            second: second_start..=second_end,
-        })
+        Self::parse(s, ReversedRanges::default())
     }
 }
 
@@ -69,15 +258,57 @@ impl Assignments {
     fn count_overlaps(&self) -> usize {
         self.0.iter().filter(|&a| a.overlaps_other()).count()
     }
+
+    fn statistics(&self) -> Statistics {
+        self.0.iter().fold(
+            Statistics {
+                crews: self.0.len(),
+                ..Default::default()
+            },
+            |mut stats, a| {
+                let wasted = a.wasted();
+                stats.overlapping += usize::from(a.overlaps_other());
+                stats.containing += usize::from(a.contains_other());
+                stats.wasted = stats.wasted.saturating_add(wasted);
+                stats.max_wasted = stats.max_wasted.max(wasted);
+                stats
+            },
+        )
+    }
+
+    fn coverage(&self) -> IntervalSet {
+        self.0
+            .iter()
+            .fold(IntervalSet::new(), |acc, a| acc.union(&a.coverage()))
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Statistics {
+    crews: usize,
+    overlapping: usize,
+    containing: usize,
+    wasted: usize,
+    max_wasted: usize,
+}
+
+impl Assignments {
+    fn parse(s: &str, reversed: ReversedRanges) -> Result<Self> {
+        libaoc::lines(s)
+            .map(|(n, l)| {
+                Assignment::parse(l, reversed)
+                    .wrap_err_with(|| format!("Invalid assignment {l:?} at line {n}"))
+            })
+            .collect::<Result<_>>()
+            .map(Self)
+    }
 }
 
 impl FromStr for Assignments {
     type Err = Report;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self(
-            s.trim().lines().map(|l| l.parse()).collect::<Result<_>>()?,
-        ))
+        Self::parse(s, ReversedRanges::default())
     }
 }
 
@@ -109,4 +340,146 @@ mod tests {
         assert_eq!(a.count_containment(), 2);
         assert_eq!(a.count_overlaps(), 4);
     }
+
+    #[rstest]
+    fn test_crews() {
+        let a: Assignments = indoc! {"
+            1-3,2-5,7-9,9-9
+            10-20
+        "}
+        .parse()
+        .unwrap();
+
+        assert_eq!(a.0[0].overlaps(), vec![(0, 1), (2, 3)]);
+        assert!(a.0[0].contains_other());
+        assert!(!a.0[1].overlaps_other());
+        assert_eq!(a.0[0].coverage().len(), 8);
+        assert_eq!(a.0[0].shared(), IntervalSet::from_iter([2..=3, 9..=9]));
+        assert_eq!(a.coverage().len(), 19);
+    }
+
+    #[rstest]
+    fn test_overlap_details(input: &str) {
+        let a: Assignments = input.parse().unwrap();
+
+        assert_eq!(
+            a.0.iter()
+                .map(Assignment::overlap_details)
+                .collect::<Vec<_>>(),
+            vec![
+                vec![],
+                vec![],
+                vec![Overlap {
+                    elves: (0, 1),
+                    shared: 7..=7,
+                    containment: Containment::Partial
+                }],
+                vec![Overlap {
+                    elves: (0, 1),
+                    shared: 3..=7,
+                    containment: Containment::FirstContainsSecond
+                }],
+                vec![Overlap {
+                    elves: (0, 1),
+                    shared: 6..=6,
+                    containment: Containment::SecondContainsFirst
+                }],
+                vec![Overlap {
+                    elves: (0, 1),
+                    shared: 4..=6,
+                    containment: Containment::Partial
+                }],
+            ]
+        );
+        assert_eq!(
+            a.0[3].overlap_details()[0].to_string(),
+            "elf 1 contains elf 2 sharing 3-7 (5 sections)"
+        );
+        assert_eq!(
+            a.statistics(),
+            Statistics {
+                crews: 6,
+                overlapping: 4,
+                containing: 2,
+                wasted: 1 + 5 + 1 + 3,
+                max_wasted: 5,
+            }
+        );
+    }
+
+    #[rstest]
+    #[case(
+        "2-4,6-8\n2-4,6-8x\n",
+        "Invalid assignment \"2-4,6-8x\" at line 2",
+        "Invalid range \"6-8x\""
+    )]
+    #[case(
+        "x2-4,6-8\n",
+        "Invalid assignment \"x2-4,6-8\" at line 1",
+        "Invalid range \"x2-4\""
+    )]
+    #[case(
+        "2-4,,6-8\n",
+        "Invalid assignment \"2-4,,6-8\" at line 1",
+        "Invalid range \"\""
+    )]
+    #[case(
+        "2-4\n5-2,6-8\n",
+        "Invalid assignment \"5-2,6-8\" at line 2",
+        "Reversed range \"5-2\""
+    )]
+    #[case(
+        "1-99999999999999999999999\n",
+        "Invalid assignment \"1-99999999999999999999999\" at line 1",
+        "number too large to fit in target type"
+    )]
+    #[case(
+        "\n2-4,6-8\n",
+        "Invalid assignment \"\" at line 1",
+        "Invalid range \"\""
+    )]
+    #[case(
+        "2-4,6-8\n\n2-4,6-8\n",
+        "Invalid assignment \"\" at line 2",
+        "Invalid range \"\""
+    )]
+    #[case(
+        "2-4,6-8\n 2-4,6-8\n",
+        "Invalid assignment \" 2-4,6-8\" at line 2",
+        "Invalid range \" 2-4\""
+    )]
+    fn test_invalid(#[case] input: &str, #[case] message: &str, #[case] cause: &str) {
+        let err = input.parse::<Assignments>().err().unwrap();
+
+        assert_eq!(err.to_string(), message);
+        assert_eq!(err.root_cause().to_string(), cause);
+    }
+
+    #[rstest]
+    fn test_max_section() {
+        let a: Assignments = "5-18446744073709551615,0-18446744073709551615\n"
+            .parse()
+            .unwrap();
+        let overlap = &a.0[0].overlap_details()[0];
+
+        assert_eq!(overlap.wasted(), usize::MAX - 4);
+        assert_eq!(a.0[0].wasted(), usize::MAX - 4);
+        assert_eq!(a.coverage().len(), usize::MAX);
+        assert_eq!(a.statistics().max_wasted, usize::MAX - 4);
+    }
+
+    #[rstest]
+    fn test_trailing_blank_lines() {
+        let a: Assignments = "2-4,6-8\n\n  \n".parse().unwrap();
+
+        assert_eq!(a.0.len(), 1);
+    }
+
+    #[rstest]
+    fn test_swap_reversed() {
+        let a = Assignments::parse("8-2,3-7\n", ReversedRanges::Swap).unwrap();
+
+        assert_eq!(a.0[0].ranges, vec![2..=8, 3..=7]);
+        assert!(a.0[0].contains_other());
+    }
 }
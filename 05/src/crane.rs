@@ -0,0 +1,122 @@
+use super::*;
+
+/// Model of a crane rearranging the stacks
+pub trait Crane {
+    /// Executes a single move, returning the number of lifts it took
+    fn lift(&self, stacks: &mut Stacks, m: &Move) -> Result<usize>;
+}
+
+/// Moves one crate at a time
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn lift(&self, stacks: &mut Stacks, m: &Move) -> Result<usize> {
+        for _ in 0..m.count {
+            let item = stacks.pop(m.from)?;
+            stacks.push(m.to, item);
+        }
+        Ok(m.count)
+    }
+}
+
+/// Moves all crates of a move at once, keeping their order
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn lift(&self, stacks: &mut Stacks, m: &Move) -> Result<usize> {
+        let items = stacks.take(m.from, m.count)?;
+        stacks.put(m.to, items);
+        Ok(1)
+    }
+}
+
+/// Moves at most `capacity` crates at once, keeping their order
+pub struct Limited {
+    pub capacity: usize,
+}
+
+impl Crane for Limited {
+    fn lift(&self, stacks: &mut Stacks, m: &Move) -> Result<usize> {
+        let mut remaining = m.count;
+        let mut lifts = 0;
+        while remaining > 0 {
+            let count = remaining.min(self.capacity);
+            let items = stacks.take(m.from, count)?;
+            stacks.put(m.to, items);
+            remaining -= count;
+            lifts += 1;
+        }
+        Ok(lifts)
+    }
+}
+
+/// Moves all crates of a move at once, putting them down upside down
+pub struct Reversing;
+
+impl Crane for Reversing {
+    fn lift(&self, stacks: &mut Stacks, m: &Move) -> Result<usize> {
+        let mut items = stacks.take(m.from, m.count)?;
+        items.reverse();
+        stacks.put(m.to, items);
+        Ok(1)
+    }
+}
+
+/// Parses a crane model, one of `9000`, `9001`, `limited:<capacity>` or
+/// `reversing`
+pub fn parse(name: &str) -> Result<Box<dyn Crane>> {
+    match name.split_once(':') {
+        None if name == "9000" => Ok(Box::new(CrateMover9000)),
+        None if name == "9001" => Ok(Box::new(CrateMover9001)),
+        None if name == "reversing" => Ok(Box::new(Reversing)),
+        Some(("limited", capacity)) => {
+            let capacity = capacity
+                .parse()
+                .wrap_err_with(|| format!("Invalid capacity {capacity:?}"))?;
+            if capacity == 0 {
+                return Err(eyre!("The capacity of a crane cannot be 0"));
+            }
+            Ok(Box::new(Limited { capacity }))
+        }
+        _ => Err(eyre!("Unknown crane {name:?}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    use super::*;
+
+    #[rstest]
+    #[case("9000", "DCB", 3)]
+    #[case("9001", "BCD", 1)]
+    #[case("limited:2", "CDB", 2)]
+    #[case("limited:3", "BCD", 1)]
+    #[case("reversing", "DCB", 1)]
+    fn test_cranes(#[case] name: &str, #[case] moved: &str, #[case] lifts: usize) {
+        let mut stacks = Stacks(vec![
+            vec!["A".into(), "B".into(), "C".into(), "D".into()],
+            vec![],
+        ]);
+        let crane = parse(name).unwrap();
+        let m = Move {
+            count: 3,
+            from: 1,
+            to: 2,
+        };
+
+        assert_eq!(crane.lift(&mut stacks, &m).unwrap(), lifts);
+        assert_eq!(stacks.0[0], vec!["A".to_string()]);
+        assert_eq!(stacks.0[1].concat(), moved);
+    }
+
+    #[rstest]
+    #[case("9002", "Unknown crane \"9002\"")]
+    #[case("limited:0", "The capacity of a crane cannot be 0")]
+    #[case("limited:x", "Invalid capacity \"x\"")]
+    fn test_invalid(#[case] name: &str, #[case] expected: &str) {
+        assert_eq!(parse(name).err().unwrap().to_string(), expected);
+    }
+}
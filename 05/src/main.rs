@@ -1,30 +1,105 @@
-use std::str::FromStr;
+use std::{
+    fmt::{self, Display},
+    ops::Range,
+    str::FromStr,
+};
 
-use color_eyre::{eyre::ContextCompat, Report, Result};
-use itertools::{iterate, Itertools};
+use color_eyre::{
+    eyre::{eyre, ContextCompat, WrapErr},
+    Report, Result,
+};
+use itertools::Itertools;
 use once_cell::sync::Lazy;
 use regex::Regex;
 
+use crane::Crane;
+
+mod crane;
+mod search;
+
 fn main() -> Result<()> {
     let input = libaoc::init()?;
-    let (stacks, moves) = parse(&input).unwrap();
+    let (stacks, moves) = parse(&input)?;
+
+    let options = libaoc::options();
+    let step = options.flag("--step");
+    let every = options.parse("--every")?.unwrap_or(1);
+    if every == 0 {
+        return Err(eyre!("--every needs a positive number of moves"));
+    }
+
+    let cranes = match options.value("--crane") {
+        Some(name) => vec![crane::parse(name)?],
+        None => vec![
+            Box::new(crane::CrateMover9000) as Box<dyn Crane>,
+            Box::new(crane::CrateMover9001),
+        ],
+    };
+
+    let goal = match (options.value("--solve-top"), options.value("--solve")) {
+        (Some(top), _) => Some(search::Goal::Top(top.into())),
+        (None, Some(path)) => Some(search::Goal::Arrangement(
+            std::fs::read_to_string(path)
+                .wrap_err_with(|| format!("Failed to read from {path:?}"))?
+                .parse()
+                .wrap_err_with(|| format!("Invalid final arrangement in {path:?}"))?,
+        )),
+        (None, None) => None,
+    };
+    if let Some(goal) = goal {
+        let budget = search::Budget {
+            moves: options.parse("--budget")?.unwrap_or(10),
+            states: options.parse("--max-states")?.unwrap_or(100_000),
+        };
+        for crane in cranes {
+            let moves = search::solve(&stacks, &goal, crane.as_ref(), budget)?;
+            println!("Found a procedure of {} moves", moves.len());
+            for m in moves {
+                println!("{m}");
+            }
+        }
+        return Ok(());
+    }
 
-    let mut part1 = stacks.clone();
-    part1.execute(&moves)?;
-    println!("The crates on the top are {}", part1.top());
-    let mut part2 = stacks;
-    part2.execute_batched(&moves)?;
-    println!("The crates on the top are {}", part2.top());
+    if options.flag("--dry-run") {
+        // Every crane moves the same crates, so one of them is enough to
+        // check whether the stacks hold enough of them
+        let errors = stacks.dry_run(cranes[0].as_ref(), &moves);
+        for err in &errors {
+            println!("{err:#}\n");
+        }
+        if !errors.is_empty() {
+            return Err(eyre!("The procedure is not feasible"));
+        }
+        println!("The procedure is feasible");
+        return Ok(());
+    }
+
+    for crane in cranes {
+        let mut stacks = stacks.clone();
+        let lifts = if step {
+            println!("{stacks}");
+            stacks.execute_steps(crane.as_ref(), &moves, |n, m, stacks| {
+                if (n + 1) % every == 0 || n + 1 == moves.len() {
+                    println!("{m} (step {})\n{stacks}", n + 1);
+                }
+            })?
+        } else {
+            stacks.execute(crane.as_ref(), &moves)?
+        };
+        println!("The crates on the top are {}", stacks.top());
+        println!("Moving them took {lifts} lifts");
+    }
 
     Ok(())
 }
 
-static CRATES: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"((\[(?P<crate>\w)\] ?)|(?P<empty>    ))").unwrap());
+static CRATES: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[(?P<crate>[^\[\]\s]+)\]").unwrap());
+static LABELS: Lazy<Regex> = Lazy::new(|| Regex::new(r"\S+").unwrap());
 static MOVES: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"move (?P<count>\d+) from (?P<from>\d+) to (?P<to>\d+)").unwrap());
+    Lazy::new(|| Regex::new(r"^move (?P<count>\d+) from (?P<from>\d+) to (?P<to>\d+)$").unwrap());
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Stacks(Vec<Vec<String>>);
 
 impl Stacks {
@@ -45,47 +120,188 @@ impl Stacks {
             .fold(String::new(), |acc, s| acc + s)
     }
 
-    fn execute(&mut self, moves: &[Move]) -> Result<()> {
-        for m in moves {
-            for _ in 0..m.count {
-                let item = self.pop(m.from)?;
-                self.push(m.to, item);
+    /// Removes the topmost `count` crates of a column
+    fn take(&mut self, col: usize, count: usize) -> Result<Vec<String>> {
+        let stack = &mut self.0[col - 1];
+        let start = stack.len().checked_sub(count).wrap_err_with(|| {
+            format!(
+                "Cannot take {count} crates from column {col} holding {}",
+                stack.len()
+            )
+        })?;
+        Ok(stack.drain(start..).collect())
+    }
+
+    fn put(&mut self, col: usize, mut items: Vec<String>) {
+        self.0[col - 1].append(&mut items)
+    }
+
+    /// Checks whether a move can be executed on the current stacks
+    fn check(&self, m: &Move) -> Result<()> {
+        if m.count == 0 {
+            return Err(eyre!("Cannot move 0 crates, at least one is needed"));
+        }
+        for col in [m.from, m.to] {
+            if !(1..=self.0.len()).contains(&col) {
+                return Err(eyre!(
+                    "Stack {col} does not exist, there are {} stacks",
+                    self.0.len()
+                ));
             }
         }
-
+        let available = self.0[m.from - 1].len();
+        if m.count > available {
+            return Err(eyre!(
+                "Cannot move {} crates from stack {} holding {available}",
+                m.count,
+                m.from
+            ));
+        }
         Ok(())
     }
 
-    fn execute_batched(&mut self, moves: &[Move]) -> Result<()> {
-        for m in moves {
-            let stack = &mut self.0[m.from - 1];
-            let mut items: Vec<_> = stack.drain(stack.len() - m.count..stack.len()).collect();
-            let stack = &mut self.0[m.to - 1];
-            stack.append(&mut items);
+    /// Executes a single move from the given line after checking it, leaving
+    /// the stacks untouched if it is invalid
+    fn execute_move(&mut self, crane: &dyn Crane, line: usize, m: &Move) -> Result<usize> {
+        self.check(m).wrap_err_with(|| {
+            format!("Cannot execute \"{m}\" at line {line}, the stacks were\n{self}")
+        })?;
+        crane.lift(self, m)
+    }
+
+    /// Executes the moves on a copy of the stacks, skipping and collecting
+    /// every move that cannot be executed
+    fn dry_run(&self, crane: &dyn Crane, moves: &[(usize, Move)]) -> Vec<Report> {
+        let mut stacks = self.clone();
+        moves
+            .iter()
+            .filter_map(|(line, m)| stacks.execute_move(crane, *line, m).err())
+            .collect()
+    }
+
+    /// Executes all moves with the given crane, returning the number of lifts
+    fn execute(&mut self, crane: &dyn Crane, moves: &[(usize, Move)]) -> Result<usize> {
+        self.execute_steps(crane, moves, |_, _, _| ())
+    }
+
+    /// Executes all moves, calling `on_step` with the state after each of them
+    fn execute_steps(
+        &mut self,
+        crane: &dyn Crane,
+        moves: &[(usize, Move)],
+        mut on_step: impl FnMut(usize, &Move, &Self),
+    ) -> Result<usize> {
+        let mut lifts = 0;
+        for (n, (line, m)) in moves.iter().enumerate() {
+            lifts += self.execute_move(crane, *line, m)?;
+            on_step(n, m, self);
         }
+        Ok(lifts)
+    }
+}
 
-        Ok(())
+/// Draws the stacks the same way as the puzzle input does
+impl Display for Stacks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .0
+            .iter()
+            .flatten()
+            .map(|c| c.chars().count())
+            .max()
+            .unwrap_or(1);
+        let height = self.0.iter().map(Vec::len).max().unwrap_or(0);
+
+        for row in (0..height).rev() {
+            let line = self
+                .0
+                .iter()
+                .map(|s| match s.get(row) {
+                    Some(c) => format!("[{c:^width$}]"),
+                    None => " ".repeat(width + 2),
+                })
+                .join(" ");
+            writeln!(f, "{line}")?;
+        }
+        let legend = (1..=self.0.len())
+            .map(|n| format!("{n:^w$}", w = width + 2))
+            .join(" ");
+        writeln!(f, "{legend}")
     }
 }
 
+/// Character positions spanned by a regex match
+fn span(line: &str, m: regex::Match) -> Range<usize> {
+    let start = line[..m.start()].chars().count();
+    start..start + m.as_str().chars().count()
+}
+
 impl FromStr for Stacks {
     type Err = Report;
 
     fn from_str(stacks: &str) -> Result<Self, Self::Err> {
-        let mut stack_lines = stacks.lines().rev();
-        let legend = stack_lines.next().wrap_err("invalid input")?;
-        let columns = (legend.len() + 1) / 4;
-
-        let mut stacks: Vec<Vec<String>> = iterate((), |_| ())
-            .take(columns * (stacks.lines().count() - 1))
-            .map(|_| Vec::new())
-            .collect();
-
-        for line in stack_lines {
-            for (j, c) in CRATES.captures_iter(line).enumerate() {
-                if let Some(c) = c.name("crate") {
-                    stacks[j].push(c.as_str().into());
+        let lines: Vec<&str> = stacks.lines().collect();
+        let (legend, crate_lines) = lines.split_last().wrap_err("Missing stack drawing")?;
+
+        let labels = LABELS
+            .find_iter(legend)
+            .enumerate()
+            .map(|(n, label)| {
+                if label.as_str() != (n + 1).to_string() {
+                    return Err(eyre!(
+                        "Expected stack label {}, got {:?} at line {}",
+                        n + 1,
+                        label.as_str(),
+                        lines.len()
+                    ));
                 }
+                Ok(span(legend, label))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if labels.is_empty() {
+            return Err(eyre!("Missing stack labels at line {}", lines.len()));
+        }
+
+        let mut stacks: Vec<Vec<String>> = vec![Vec::new(); labels.len()];
+
+        for (row, (n, line)) in crate_lines.iter().enumerate().rev().enumerate() {
+            let mut covered = vec![];
+            for c in CRATES.captures_iter(line) {
+                let whole = c.get(0).unwrap();
+                let name = c.name("crate").unwrap().as_str();
+                let cells = span(line, whole);
+                let (col, _) = labels
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, l)| l.start < cells.end && cells.start < l.end)
+                    .exactly_one()
+                    .map_err(|_| {
+                        eyre!(
+                            "Crate {:?} at line {}, column {} is not above a single stack",
+                            whole.as_str(),
+                            n + 1,
+                            cells.start + 1
+                        )
+                    })?;
+                if stacks[col].len() != row {
+                    return Err(eyre!(
+                        "Crate {:?} at line {} is floating above a gap in stack {}",
+                        whole.as_str(),
+                        n + 1,
+                        col + 1
+                    ));
+                }
+                stacks[col].push(name.into());
+                covered.push(cells);
+            }
+            if let Some((pos, c)) = line.chars().enumerate().find(|(pos, c)| {
+                !c.is_whitespace() && !covered.iter().any(|cells| cells.contains(pos))
+            }) {
+                return Err(eyre!(
+                    "Unexpected {c:?} at line {}, column {}",
+                    n + 1,
+                    pos + 1
+                ));
             }
         }
 
@@ -93,12 +309,19 @@ impl FromStr for Stacks {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct Move {
     count: usize,
     from: usize,
     to: usize,
 }
 
+impl Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from, self.to)
+    }
+}
+
 impl FromStr for Move {
     type Err = Report;
 
@@ -114,7 +337,8 @@ impl FromStr for Move {
     }
 }
 
-fn parse(input: &str) -> Result<(Stacks, Vec<Move>)> {
+/// Parses the drawing and the moves of the procedure, along with their lines
+fn parse(input: &str) -> Result<(Stacks, Vec<(usize, Move)>)> {
     let (stacks, procedure) = input
         .trim_end()
         .split("\n\n")
@@ -122,10 +346,17 @@ fn parse(input: &str) -> Result<(Stacks, Vec<Move>)> {
         .next()
         .wrap_err("Invalid input format")?;
 
+    let first_move = stacks.lines().count() + 2;
     let stacks: Stacks = stacks.parse()?;
     let moves = procedure
         .lines()
-        .map(|l| l.parse())
+        .zip(first_move..)
+        .map(|(l, line)| {
+            let m = l
+                .parse()
+                .wrap_err_with(|| format!("Invalid move at line {line}"))?;
+            Ok((line, m))
+        })
         .collect::<Result<_>>()?;
     Ok((stacks, moves))
 }
@@ -153,9 +384,164 @@ move 1 from 1 to 2\n\
         let (mut stacks, moves) = parse(input).unwrap();
 
         let mut part1 = stacks.clone();
-        part1.execute(&moves).unwrap();
+        assert_eq!(part1.execute(&crane::CrateMover9000, &moves).unwrap(), 7);
         assert_eq!(part1.top(), "CMZ".to_string());
-        stacks.execute_batched(&moves).unwrap();
+        assert_eq!(stacks.execute(&crane::CrateMover9001, &moves).unwrap(), 4);
         assert_eq!(stacks.top(), "MCD".to_string());
     }
+
+    #[rstest]
+    fn test_display(input: &str) {
+        let (mut stacks, moves) = parse(input).unwrap();
+        let (drawing, _) = input.split_once("\n\n").unwrap();
+        assert_eq!(stacks.to_string(), format!("{drawing}\n"));
+
+        let mut steps = vec![];
+        stacks
+            .execute_steps(&crane::CrateMover9000, &moves[..2], |n, m, s| {
+                steps.push(format!("{m} (step {})\n{s}", n + 1))
+            })
+            .unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                concat!(
+                    "move 1 from 2 to 1 (step 1)\n",
+                    "[D]        \n",
+                    "[N] [C]    \n",
+                    "[Z] [M] [P]\n",
+                    " 1   2   3 \n",
+                ),
+                concat!(
+                    "move 3 from 1 to 3 (step 2)\n",
+                    "        [Z]\n",
+                    "        [N]\n",
+                    "    [C] [D]\n",
+                    "    [M] [P]\n",
+                    " 1   2   3 \n",
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_ragged_drawing() {
+        let drawing = concat!(
+            "                                    [Q]\n",
+            "[A]                                 [R]   [Y]\n",
+            "[B]     [C]                     [P] [S] [X] [Z]\n",
+            " 1   2   3   4   5   6   7   8   9  10  11  12\n",
+        );
+        let stacks: Stacks = drawing.parse().unwrap();
+
+        assert_eq!(stacks.0.len(), 12);
+        assert_eq!(stacks.top(), "ACPQXY");
+        assert_eq!(stacks.0[9], vec!["S", "R", "Q"]);
+    }
+
+    #[rstest]
+    fn test_wide_crates() {
+        let drawing = concat!(
+            "[Dx]\n",
+            "[Nx] [Cx]\n",
+            "[Zx] [Mx] [Px]\n",
+            " 1    2    3\n",
+        );
+        let stacks: Stacks = drawing.parse().unwrap();
+
+        assert_eq!(stacks.top(), "DxCxPx");
+        assert_eq!(
+            stacks.to_string(),
+            concat!(
+                "[Dx]          \n",
+                "[Nx] [Cx]     \n",
+                "[Zx] [Mx] [Px]\n",
+                " 1    2    3  \n",
+            )
+        );
+    }
+
+    #[rstest]
+    #[case(
+        "    [D]\n[N]\n 1   2\n",
+        "Crate \"[D]\" at line 1 is floating above a gap in stack 2"
+    )]
+    #[case("[N] x\n 1   2\n", "Unexpected 'x' at line 1, column 5")]
+    #[case(
+        "        [C]\n 1   2\n",
+        "Crate \"[C]\" at line 1, column 9 is not above a single stack"
+    )]
+    #[case("[N]\n 1   3\n", "Expected stack label 2, got \"3\" at line 2")]
+    #[case("[N]\n\n", "Missing stack labels at line 2")]
+    fn test_invalid_drawing(#[case] drawing: &str, #[case] expected: &str) {
+        let err = drawing.parse::<Stacks>().unwrap_err();
+
+        assert_eq!(err.to_string(), expected);
+    }
+
+    #[rstest]
+    fn test_invalid_moves(input: &str) {
+        let input = input.replace("move 3 from 1 to 3", "move 3 from 1 to 4");
+        let input = input.replace("move 1 from 1 to 2", "move 6 from 1 to 2");
+        let (mut stacks, moves) = parse(&input).unwrap();
+
+        let errors = stacks.dry_run(&crane::CrateMover9001, &moves);
+        assert_eq!(
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            vec![
+                concat!(
+                    "Cannot execute \"move 3 from 1 to 4\" at line 7, the stacks were\n",
+                    "[D]        \n",
+                    "[N] [C]    \n",
+                    "[Z] [M] [P]\n",
+                    " 1   2   3 \n",
+                ),
+                concat!(
+                    "Cannot execute \"move 6 from 1 to 2\" at line 9, the stacks were\n",
+                    "[C]        \n",
+                    "[M]        \n",
+                    "[D]        \n",
+                    "[N]        \n",
+                    "[Z]     [P]\n",
+                    " 1   2   3 \n",
+                ),
+            ]
+        );
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.root_cause().to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "Stack 4 does not exist, there are 3 stacks",
+                "Cannot move 6 crates from stack 1 holding 5",
+            ]
+        );
+
+        let err = stacks.execute(&crane::CrateMover9001, &moves).unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            errors[0].root_cause().to_string()
+        );
+
+        let (stacks, moves) =
+            parse(&input.replace("move 2 from 2 to 1", "move 0 from 2 to 1")).unwrap();
+        let errors = stacks.dry_run(&crane::CrateMover9001, &moves);
+        assert_eq!(
+            errors[1].to_string().lines().next().unwrap(),
+            "Cannot execute \"move 0 from 2 to 1\" at line 8, the stacks were"
+        );
+        assert_eq!(
+            errors[1].root_cause().to_string(),
+            "Cannot move 0 crates, at least one is needed"
+        );
+
+        let err = parse(&input.replace("move 1 from 2 to 1", "move 1 from 2 to 1 trailing junk"))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Invalid move at line 6");
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Invalid line move 1 from 2 to 1 trailing junk"
+        );
+    }
 }
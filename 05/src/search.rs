@@ -0,0 +1,241 @@
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BinaryHeap, HashMap},
+};
+
+use super::*;
+
+/// Target state of a search
+pub enum Goal {
+    /// The crates on the top of the stacks, as printed by [`Stacks::top`]
+    Top(String),
+    /// The complete final arrangement of the stacks
+    Arrangement(Stacks),
+}
+
+impl Goal {
+    fn is_reached(&self, stacks: &Stacks) -> bool {
+        match self {
+            Goal::Top(top) => stacks.top() == *top,
+            Goal::Arrangement(arrangement) => stacks == arrangement,
+        }
+    }
+
+    /// Lower bound of the moves still needed, as each move changes at most
+    /// two stacks
+    fn estimate(&self, stacks: &Stacks) -> usize {
+        match self {
+            Goal::Top(_) => usize::from(!self.is_reached(stacks)),
+            Goal::Arrangement(arrangement) => {
+                let differing = stacks
+                    .0
+                    .iter()
+                    .zip(&arrangement.0)
+                    .filter(|(a, b)| a != b)
+                    .count();
+                differing.div_ceil(2)
+            }
+        }
+    }
+
+    fn check(&self, start: &Stacks) -> Result<()> {
+        if let Goal::Arrangement(arrangement) = self {
+            if arrangement.0.len() != start.0.len() {
+                return Err(eyre!(
+                    "Expected {} stacks in the final arrangement, got {}",
+                    start.0.len(),
+                    arrangement.0.len()
+                ));
+            }
+            let crates = |s: &Stacks| s.0.iter().flatten().sorted().cloned().collect::<Vec<_>>();
+            if crates(start) != crates(arrangement) {
+                return Err(eyre!(
+                    "The final arrangement does not have the same crates as the drawing"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn successors<'a>(
+    stacks: &'a Stacks,
+    crane: &'a dyn Crane,
+) -> impl Iterator<Item = (Move, Stacks)> + 'a {
+    let columns = 1..=stacks.0.len();
+    columns
+        .clone()
+        .cartesian_product(columns)
+        .filter(|(from, to)| from != to)
+        .flat_map(move |(from, to)| {
+            (1..=stacks.0[from - 1].len()).map(move |count| Move { count, from, to })
+        })
+        .filter_map(move |m| {
+            let mut next = stacks.clone();
+            crane.lift(&mut next, &m).ok()?;
+            Some((m, next))
+        })
+}
+
+/// Bounds of the search space
+#[derive(Debug, Clone, Copy)]
+pub struct Budget {
+    /// Maximum length of the procedure
+    pub moves: usize,
+    /// Maximum number of arrangements to explore before giving up
+    pub states: usize,
+}
+
+/// Finds a shortest procedure within the budget reaching the goal using A*
+/// search
+pub fn solve(start: &Stacks, goal: &Goal, crane: &dyn Crane, budget: Budget) -> Result<Vec<Move>> {
+    goal.check(start)?;
+
+    let mut states = vec![(start.clone(), None::<(usize, Move)>)];
+    let mut best = HashMap::from([(start.clone(), 0)]);
+    let mut open = BinaryHeap::from([Reverse((goal.estimate(start), 0, 0))]);
+
+    while let Some(Reverse((_, moves, id))) = open.pop() {
+        let stacks = &states[id].0;
+        if goal.is_reached(stacks) {
+            let mut procedure = vec![];
+            let mut id = id;
+            while let Some((parent, m)) = &states[id].1 {
+                procedure.push(m.clone());
+                id = *parent;
+            }
+            procedure.reverse();
+            return Ok(procedure);
+        }
+        if best.get(stacks).is_some_and(|&b| b < moves) {
+            continue;
+        }
+
+        let next_states: Vec<_> = successors(stacks, crane).collect();
+        for (m, next) in next_states {
+            let estimate = moves + 1 + goal.estimate(&next);
+            if estimate > budget.moves {
+                continue;
+            }
+            if states.len() >= budget.states {
+                return Err(eyre!(
+                    "Gave up after exploring {} arrangements",
+                    budget.states
+                ));
+            }
+            match best.entry(next.clone()) {
+                Entry::Occupied(e) if *e.get() <= moves + 1 => continue,
+                Entry::Occupied(mut e) => {
+                    e.insert(moves + 1);
+                }
+                Entry::Vacant(e) => {
+                    e.insert(moves + 1);
+                }
+            }
+            states.push((next, Some((id, m))));
+            open.push(Reverse((estimate, moves + 1, states.len() - 1)));
+        }
+    }
+
+    Err(eyre!(
+        "No procedure reaches the goal within {} moves",
+        budget.moves
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    use super::*;
+
+    #[fixture]
+    fn start() -> Stacks {
+        "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n"
+            .parse()
+            .unwrap()
+    }
+
+    fn budget(moves: usize) -> Budget {
+        Budget {
+            moves,
+            states: 100_000,
+        }
+    }
+
+    fn replay(start: &Stacks, crane: &dyn Crane, moves: &[Move]) -> Stacks {
+        let mut stacks = start.clone();
+        let moves: Vec<_> = (1..).zip(moves.iter().cloned()).collect();
+        stacks.execute(crane, &moves).unwrap();
+        stacks
+    }
+
+    #[rstest]
+    #[case(&crane::CrateMover9000, "CMZ", 2)]
+    #[case(&crane::CrateMover9001, "MCD", 3)]
+    #[case(&crane::CrateMover9001, "DCP", 1)]
+    fn test_solve_top(
+        start: Stacks,
+        #[case] crane: &dyn Crane,
+        #[case] top: &str,
+        #[case] expected: usize,
+    ) {
+        let moves = solve(&start, &Goal::Top(top.into()), crane, budget(4)).unwrap();
+
+        assert_eq!(moves.len(), expected);
+        assert_eq!(replay(&start, crane, &moves).top(), top);
+    }
+
+    #[rstest]
+    fn test_solve_arrangement(start: Stacks) {
+        let target: Stacks = "        [Z]\n        [N]\n        [D]\n[C] [M] [P]\n 1   2   3 \n"
+            .parse()
+            .unwrap();
+        let goal = Goal::Arrangement(target.clone());
+
+        let moves = solve(&start, &goal, &crane::CrateMover9000, budget(4)).unwrap();
+        assert_eq!(replay(&start, &crane::CrateMover9000, &moves), target);
+        assert_eq!(moves.len(), 3);
+
+        let err = solve(&start, &goal, &crane::CrateMover9000, budget(2)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No procedure reaches the goal within 2 moves"
+        );
+    }
+
+    #[rstest]
+    fn test_unreachable_arrangement(start: Stacks) {
+        let target: Stacks = "[A]\n 1   2   3 \n".parse().unwrap();
+        let err = solve(
+            &start,
+            &Goal::Arrangement(target),
+            &crane::CrateMover9000,
+            budget(4),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "The final arrangement does not have the same crates as the drawing"
+        );
+    }
+
+    #[rstest]
+    fn test_state_limit(start: Stacks) {
+        let budget = Budget {
+            moves: 10,
+            states: 10,
+        };
+        let err = solve(
+            &start,
+            &Goal::Top("ZZZ".into()),
+            &crane::CrateMover9000,
+            budget,
+        )
+        .unwrap_err();
+
+        assert_eq!(err.to_string(), "Gave up after exploring 10 arrangements");
+    }
+}
@@ -0,0 +1,34 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use day06::{find_unique_pattern, find_unique_pattern_scalar, scan, MESSAGE_MARKER};
+
+/// Long noisy signal over too few symbols for a start-of-message marker,
+/// followed by one
+fn signal() -> Vec<u8> {
+    let mut rng = StdRng::seed_from_u64(2022);
+    let mut signal: Vec<u8> = (0..1_000_000)
+        .map(|_| rng.gen_range(b'a'..b'a' + 13))
+        .collect();
+    signal.extend_from_slice(b"abcdefghijklmnopqrstuvwxyz");
+    signal
+}
+
+fn message(c: &mut Criterion) {
+    let signal = signal();
+    let mut group = c.benchmark_group("message");
+
+    group.bench_function("scalar", |b| {
+        b.iter(|| find_unique_pattern_scalar(black_box(&signal), MESSAGE_MARKER))
+    });
+    group.bench_function("dispatch", |b| {
+        b.iter(|| find_unique_pattern(black_box(&signal), MESSAGE_MARKER))
+    });
+    group.bench_function("stream", |b| {
+        b.iter(|| scan(black_box(signal.as_slice()), &[MESSAGE_MARKER]).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, message);
+criterion_main!(benches);
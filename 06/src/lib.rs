@@ -0,0 +1,177 @@
+use std::io::{self, ErrorKind, Read};
+
+pub mod protocol;
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+pub mod simd;
+pub mod stats;
+
+pub const PACKET_MARKER: usize = 4;
+pub const MESSAGE_MARKER: usize = 14;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+pub fn start_of_packet(buf: &[u8]) -> Option<usize> {
+    find_unique_pattern(buf, PACKET_MARKER)
+}
+
+pub fn start_of_message(buf: &[u8]) -> Option<usize> {
+    find_unique_pattern(buf, MESSAGE_MARKER)
+}
+
+/// Finds the end of the first window of `len` distinct bytes, using the SIMD
+/// search when the `simd` feature is enabled and supports the length
+pub fn find_unique_pattern(buf: &[u8], len: usize) -> Option<usize> {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if len <= simd::MAX_LEN {
+        return simd::find_unique_pattern(buf, len);
+    }
+    find_unique_pattern_scalar(buf, len)
+}
+
+pub fn find_unique_pattern_scalar(buf: &[u8], len: usize) -> Option<usize> {
+    let mut window = Window::new(len);
+    buf.iter()
+        .position(|&byte| window.push(byte))
+        .map(|pos| pos + 1)
+}
+
+/// Sliding window over the last `len` bytes of a stream
+///
+/// Keeps a count per byte value and the number of values present more than
+/// once, so each push takes constant time whatever the window length.
+pub struct Window {
+    ring: Vec<u8>,
+    counts: [u32; 256],
+    repeated: usize,
+    seen: usize,
+}
+
+impl Window {
+    /// Panics if `len` is zero
+    pub fn new(len: usize) -> Self {
+        assert!(len > 0, "Marker length must be positive");
+        Self {
+            ring: vec![0; len],
+            counts: [0; 256],
+            repeated: 0,
+            seen: 0,
+        }
+    }
+
+    /// Empties the window, e.g. when a byte cannot be part of a marker
+    pub fn clear(&mut self) {
+        let filled = self.seen.min(self.ring.len());
+        for &byte in &self.ring[..filled] {
+            self.counts[byte as usize] = 0;
+        }
+        self.repeated = 0;
+        self.seen = 0;
+    }
+
+    /// Pushes a byte, returning whether the window is full of distinct bytes
+    pub fn push(&mut self, byte: u8) -> bool {
+        let len = self.ring.len();
+        let slot = self.seen % len;
+        if self.seen >= len {
+            let old = self.ring[slot] as usize;
+            self.counts[old] -= 1;
+            if self.counts[old] == 1 {
+                self.repeated -= 1;
+            }
+        }
+        self.ring[slot] = byte;
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.repeated += 1;
+        }
+        self.seen += 1;
+
+        self.seen >= len && self.repeated == 0
+    }
+}
+
+/// Finds the end of the first marker of each length in a stream
+///
+/// The stream is read in fixed-size chunks into a single reused buffer,
+/// keeping the end of the previous chunk around for markers spanning two of
+/// them, and stops as soon as every marker has been found.
+pub fn scan<R: Read>(mut reader: R, lens: &[usize]) -> io::Result<Vec<Option<usize>>> {
+    let keep = lens.iter().max().map_or(0, |len| len.saturating_sub(1));
+    let mut found = vec![None; lens.len()];
+    let mut buf = vec![0; keep + CHUNK_SIZE];
+    // Number of bytes of `buf` holding data from the stream
+    let mut filled = 0;
+    // Position of `buf[0]` in the stream
+    let mut offset = 0;
+
+    while found.iter().any(Option::is_none) {
+        let start = filled;
+        match reader.read(&mut buf[start..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+
+        for (&len, found) in lens.iter().zip(&mut found) {
+            if found.is_some() {
+                continue;
+            }
+            // Windows ending before `start` were searched with the previous chunk
+            let from = (start + 1).saturating_sub(len);
+            *found = find_unique_pattern(&buf[from..filled], len).map(|end| offset + from + end);
+        }
+
+        let drop = filled.saturating_sub(keep);
+        buf.copy_within(drop..filled, 0);
+        filled -= drop;
+        offset += drop;
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    use super::*;
+
+    /// Reader handing out a single byte at a time
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((&byte, rest)) if !buf.is_empty() => {
+                    buf[0] = byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[rstest]
+    #[case("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19)]
+    #[case("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23)]
+    #[case("nppdvjthqldpwncqszvftbrmjlhg", 6, 23)]
+    #[case("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29)]
+    #[case("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26)]
+    fn test_start(#[case] buf: &str, #[case] packet_start: usize, #[case] message_start: usize) {
+        assert_eq!(start_of_packet(buf.as_bytes()), Some(packet_start));
+        assert_eq!(start_of_message(buf.as_bytes()), Some(message_start));
+    }
+
+    #[rstest]
+    #[case("mjqjpqmgbljsphdztnvjfqwrcgsmlb", &[Some(7), Some(19)])]
+    #[case("aaaaaaaaaaaaaaaaaaaabcd", &[Some(23), None])]
+    #[case("abc", &[None, None])]
+    fn test_scan(#[case] buf: &str, #[case] expected: &[Option<usize>]) {
+        let lens = [PACKET_MARKER, MESSAGE_MARKER];
+
+        assert_eq!(scan(buf.as_bytes(), &lens).unwrap(), expected);
+        assert_eq!(scan(Trickle(buf.as_bytes()), &lens).unwrap(), expected);
+    }
+}
@@ -1,47 +1,86 @@
-use color_eyre::{eyre::ContextCompat, Result};
-use itertools::Itertools;
+use std::fs::File;
+
+use color_eyre::{
+    eyre::{eyre, ContextCompat, WrapErr},
+    Result,
+};
+
+use day06::{protocol, scan, stats, MESSAGE_MARKER, PACKET_MARKER};
 
 fn main() -> Result<()> {
-    let input = libaoc::init()?;
+    libaoc::setup()?;
 
-    let start = start_of_packet(&input).wrap_err("No start-of-packet found")?;
+    let path = libaoc::input_path()?;
+    let options = libaoc::options();
+    if options.flag("--decode") {
+        return decode(&path, &options);
+    }
+    if ["--markers", "--longest", "--histogram"]
+        .iter()
+        .any(|name| options.value(name).is_some() || options.flag(name))
+    {
+        return analyze(&path, &options);
+    }
+
+    let file = File::open(&path).wrap_err_with(|| format!("Failed to open {path:?}"))?;
+    let starts = scan(file, &[PACKET_MARKER, MESSAGE_MARKER])
+        .wrap_err_with(|| format!("Failed to read from {path:?}"))?;
+
+    let start = starts[0].wrap_err("No start-of-packet found")?;
     println!("Start-of-packet found at {start}");
-    let start = start_of_message(&input).wrap_err("No start-of-message found")?;
+    let start = starts[1].wrap_err("No start-of-message found")?;
     println!("Start-of-message found at {start}");
 
     Ok(())
 }
 
-fn start_of_packet(buf: &str) -> Option<usize> {
-    find_unique_pattern(buf, 4)
-}
+/// Prints the frames of the datastream
+fn decode(path: &str, options: &libaoc::Options) -> Result<()> {
+    let protocol = protocol::Protocol::new(
+        options.parse("--packet-len")?.unwrap_or(PACKET_MARKER),
+        options.parse("--message-len")?.unwrap_or(MESSAGE_MARKER),
+        options.parse("--alphabet")?.unwrap_or_default(),
+    )?;
+    let buf = std::fs::read(path).wrap_err_with(|| format!("Failed to read from {path:?}"))?;
 
-fn start_of_message(buf: &str) -> Option<usize> {
-    find_unique_pattern(buf, 14)
+    for frame in protocol.decode(buf.trim_ascii_end()) {
+        println!("{frame}");
+    }
+    Ok(())
 }
 
-fn find_unique_pattern(buf: &str, len: usize) -> Option<usize> {
-    buf.as_bytes()
-        .windows(len)
-        .position(|win| win.iter().unique().count() == len)
-        .map(|pos| pos + len)
-}
+/// Prints the marker statistics selected on the command line
+fn analyze(path: &str, options: &libaoc::Options) -> Result<()> {
+    let buf = std::fs::read(path).wrap_err_with(|| format!("Failed to read from {path:?}"))?;
+    let buf = buf.trim_ascii_end();
 
-#[cfg(test)]
-mod tests {
-    use pretty_assertions::assert_eq;
-    use rstest::*;
-
-    use super::*;
-
-    #[rstest]
-    #[case("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19)]
-    #[case("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23)]
-    #[case("nppdvjthqldpwncqszvftbrmjlhg", 6, 23)]
-    #[case("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29)]
-    #[case("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26)]
-    fn test_start(#[case] buf: &str, #[case] packet_start: usize, #[case] message_start: usize) {
-        assert_eq!(start_of_packet(buf), Some(packet_start));
-        assert_eq!(start_of_message(buf), Some(message_start));
+    let markers = options.parse::<usize>("--markers")?;
+    if markers.is_none() && options.flag("--markers") {
+        return Err(eyre!("--markers needs a marker length"));
+    }
+    if let Some(len) = markers {
+        if len == 0 {
+            return Err(eyre!("Marker length must be positive"));
+        }
+        let positions = stats::marker_positions(buf, len);
+        println!("{} markers of length {len}", positions.len());
+        let positions: Vec<_> = positions.iter().map(usize::to_string).collect();
+        println!("{}", positions.join(" "));
     }
+    if options.flag("--longest") {
+        let run = stats::longest_distinct_run(buf);
+        println!(
+            "Longest distinct run: {} bytes at {}: {:?}",
+            run.len(),
+            run.start,
+            String::from_utf8_lossy(&buf[run.clone()])
+        );
+    }
+    if options.flag("--histogram") {
+        println!("Run length | Count");
+        for (len, count) in stats::histogram(buf) {
+            println!("{len:>10} | {count}");
+        }
+    }
+    Ok(())
 }
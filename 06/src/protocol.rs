@@ -0,0 +1,308 @@
+use std::{fmt, ops::Range, str::FromStr};
+
+use color_eyre::{eyre::eyre, Report, Result};
+
+use super::*;
+
+/// Set of bytes markers can be made of
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet([bool; 256]);
+
+impl Alphabet {
+    pub fn contains(&self, byte: u8) -> bool {
+        self.0[byte as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.iter().filter(|&&b| b).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        "a-z".parse().unwrap()
+    }
+}
+
+/// Parses a list of ASCII symbols and `a-z` like ranges, e.g. `a-zA-Z0-9_`
+impl FromStr for Alphabet {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(c) = s.chars().find(|c| !c.is_ascii()) {
+            return Err(eyre!("Symbol {c:?} in alphabet is not ASCII"));
+        }
+        let mut symbols = [false; 256];
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let (first, last, step) = match bytes.get(i..i + 3) {
+                Some(&[first, b'-', last]) => (first, last, 3),
+                _ => (bytes[i], bytes[i], 1),
+            };
+            if first > last {
+                return Err(eyre!(
+                    "Invalid range {}-{} in alphabet",
+                    first as char,
+                    last as char
+                ));
+            }
+            symbols[first as usize..=last as usize].fill(true);
+            i += step;
+        }
+        if symbols.iter().all(|&b| !b) {
+            return Err(eyre!("Empty alphabet"));
+        }
+        Ok(Self(symbols))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    Packet,
+    Message,
+}
+
+impl FrameKind {
+    fn next(self) -> Self {
+        match self {
+            Self::Packet => Self::Message,
+            Self::Message => Self::Packet,
+        }
+    }
+}
+
+impl fmt::Display for FrameKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Packet => write!(f, "packet"),
+            Self::Message => write!(f, "message"),
+        }
+    }
+}
+
+/// Marker lengths and alphabet of the communication protocol
+///
+/// A datastream alternates packets and messages, each frame starting with
+/// its marker and carrying everything up to the marker of the next frame.
+#[derive(Debug, Clone)]
+pub struct Protocol {
+    pub packet: usize,
+    pub message: usize,
+    pub alphabet: Alphabet,
+}
+
+impl Default for Protocol {
+    fn default() -> Self {
+        Self {
+            packet: PACKET_MARKER,
+            message: MESSAGE_MARKER,
+            alphabet: Alphabet::default(),
+        }
+    }
+}
+
+impl Protocol {
+    pub fn new(packet: usize, message: usize, alphabet: Alphabet) -> Result<Self> {
+        for (kind, len) in [(FrameKind::Packet, packet), (FrameKind::Message, message)] {
+            if len == 0 || len > alphabet.len() {
+                return Err(eyre!(
+                    "A {kind} marker of {len} distinct symbols cannot be made from an alphabet of {}",
+                    alphabet.len()
+                ));
+            }
+        }
+        Ok(Self {
+            packet,
+            message,
+            alphabet,
+        })
+    }
+
+    pub fn marker_len(&self, kind: FrameKind) -> usize {
+        match kind {
+            FrameKind::Packet => self.packet,
+            FrameKind::Message => self.message,
+        }
+    }
+
+    /// Splits a datastream into its frames
+    pub fn decode<'a>(&'a self, buf: &'a [u8]) -> Decoder<'a> {
+        Decoder {
+            protocol: self,
+            buf,
+            pending: None,
+            started: false,
+        }
+    }
+
+    /// Finds the first marker of the given kind starting at `from`
+    fn find(&self, buf: &[u8], kind: FrameKind, from: usize) -> Option<Range<usize>> {
+        let len = self.marker_len(kind);
+        let mut window = Window::new(len);
+        buf[from..]
+            .iter()
+            .position(|&byte| {
+                if self.alphabet.contains(byte) {
+                    window.push(byte)
+                } else {
+                    window.clear();
+                    false
+                }
+            })
+            .map(|pos| from + pos + 1 - len..from + pos + 1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame<'a> {
+    pub kind: FrameKind,
+    /// Position of the marker in the datastream
+    pub offset: usize,
+    pub marker: &'a [u8],
+    pub payload: &'a [u8],
+}
+
+impl fmt::Display for Frame<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}: marker {:?}, {} bytes",
+            self.kind,
+            self.offset,
+            String::from_utf8_lossy(self.marker),
+            self.payload.len()
+        )
+    }
+}
+
+/// Iterator over the frames of a datastream, skipping anything before the
+/// first start-of-packet marker
+pub struct Decoder<'a> {
+    protocol: &'a Protocol,
+    buf: &'a [u8],
+    /// Marker of the next frame, found while delimiting the previous one
+    pending: Option<(FrameKind, Range<usize>)>,
+    started: bool,
+}
+
+impl<'a> Iterator for Decoder<'a> {
+    type Item = Frame<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            self.pending = self
+                .protocol
+                .find(self.buf, FrameKind::Packet, 0)
+                .map(|marker| (FrameKind::Packet, marker));
+        }
+
+        let (kind, marker) = self.pending.take()?;
+        self.pending = self
+            .protocol
+            .find(self.buf, kind.next(), marker.end)
+            .map(|next| (kind.next(), next));
+        let end = self
+            .pending
+            .as_ref()
+            .map_or(self.buf.len(), |(_, next)| next.start);
+
+        Some(Frame {
+            kind,
+            offset: marker.start,
+            marker: &self.buf[marker.clone()],
+            payload: &self.buf[marker.end..end],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    use super::*;
+
+    fn frames(protocol: &Protocol, buf: &str) -> Vec<(FrameKind, usize, String, String)> {
+        protocol
+            .decode(buf.as_bytes())
+            .map(|frame| {
+                (
+                    frame.kind,
+                    frame.offset,
+                    String::from_utf8(frame.marker.to_vec()).unwrap(),
+                    String::from_utf8(frame.payload.to_vec()).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[rstest]
+    fn test_decode() {
+        let protocol = Protocol::new(2, 3, Alphabet::default()).unwrap();
+
+        assert_eq!(
+            frames(&protocol, "aabaaaabcccabxx"),
+            vec![
+                (FrameKind::Packet, 1, "ab".into(), "aaa".into()),
+                (FrameKind::Message, 6, "abc".into(), "c".into()),
+                (FrameKind::Packet, 10, "ca".into(), "bxx".into()),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_decode_sample() {
+        assert_eq!(
+            frames(&Protocol::default(), "mjqjpqmgbljsphdztnvjfqwrcgsmlb"),
+            vec![
+                (FrameKind::Packet, 3, "jpqm".into(), "gblj".into()),
+                (FrameKind::Message, 11, "sphdztnvjfqwrc".into(), "".into()),
+                (FrameKind::Packet, 25, "gsml".into(), "b".into()),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("ab-d", Ok(4))]
+    #[case("a-", Ok(2))]
+    #[case("abc", Ok(3))]
+    #[case("a-zA-Z", Ok(52))]
+    #[case("0-9-", Ok(11))]
+    #[case("z-a", Err("Invalid range z-a in alphabet"))]
+    #[case("", Err("Empty alphabet"))]
+    #[case("abcé", Err("Symbol 'é' in alphabet is not ASCII"))]
+    #[case("a-é", Err("Symbol 'é' in alphabet is not ASCII"))]
+    fn test_alphabet(#[case] s: &str, #[case] expected: Result<usize, &str>) {
+        assert_eq!(
+            s.parse::<Alphabet>()
+                .map(|a| a.len())
+                .map_err(|e| e.to_string()),
+            expected.map_err(String::from)
+        );
+    }
+
+    #[rstest]
+    fn test_alphabet_breaks_markers() {
+        let protocol = Protocol::new(3, 3, "a-c".parse().unwrap()).unwrap();
+        let decoded: Vec<_> = protocol.decode(b"abdcabcdd").collect();
+
+        assert_eq!(decoded[0].offset, 3);
+        assert_eq!(decoded[0].payload, b"cdd");
+    }
+
+    #[rstest]
+    fn test_marker_too_long() {
+        let err = Protocol::new(4, 14, "a-f".parse().unwrap()).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "A message marker of 14 distinct symbols cannot be made from an alphabet of 6"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::arch::x86_64::{
+    __m128i, _mm_and_si128, _mm_andnot_si128, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_or_si128,
+    _mm_set1_epi8, _mm_storeu_si128,
+};
+
+/// Longest marker fitting in a single SSE2 register
+pub const MAX_LEN: usize = 16;
+
+/// Finds the end of the first window of `len` distinct bytes
+///
+/// The distance of each byte to its previous occurrence is computed for 16
+/// bytes at once by comparing them with the `len - 1` preceding blocks, then
+/// a single pass over the distances tracks where the current all-distinct run
+/// starts. Bytes without enough of the buffer around them for full register
+/// loads get their distance computed one at a time.
+///
+/// Panics if `len` is zero or above [`MAX_LEN`]
+pub fn find_unique_pattern(buf: &[u8], len: usize) -> Option<usize> {
+    assert!(
+        (1..=MAX_LEN).contains(&len),
+        "Marker length must be between 1 and {MAX_LEN}"
+    );
+
+    let mut start = 0;
+    let mut is_marker_end = |pos: usize, distance: usize| {
+        start = start.max((pos + 1).saturating_sub(distance));
+        pos + 1 - start >= len
+    };
+
+    let mut pos = 0;
+    while pos < buf.len().min(len - 1) {
+        if is_marker_end(pos, distance(buf, pos, len)) {
+            return Some(pos + 1);
+        }
+        pos += 1;
+    }
+    while pos + MAX_LEN <= buf.len() {
+        for (lane, distance) in distances(buf, pos, len).into_iter().enumerate() {
+            if is_marker_end(pos + lane, distance as usize) {
+                return Some(pos + lane + 1);
+            }
+        }
+        pos += MAX_LEN;
+    }
+    while pos < buf.len() {
+        if is_marker_end(pos, distance(buf, pos, len)) {
+            return Some(pos + 1);
+        }
+        pos += 1;
+    }
+    None
+}
+
+/// Distance of the byte at `pos` to its previous occurrence, or `len` if it
+/// does not occur in the `len - 1` bytes before
+fn distance(buf: &[u8], pos: usize, len: usize) -> usize {
+    (1..len.min(pos + 1))
+        .find(|&shift| buf[pos - shift] == buf[pos])
+        .unwrap_or(len)
+}
+
+/// Same as [`distance`] for the 16 bytes starting at `pos`, which must have
+/// at least `len - 1` bytes before them
+fn distances(buf: &[u8], pos: usize, len: usize) -> [u8; MAX_LEN] {
+    let block = load(&buf[pos..]);
+    let mut distances = [0; MAX_LEN];
+    // SAFETY: SSE2 is part of the x86_64 baseline, and the store writes a
+    // full register to an array of that size
+    unsafe {
+        let mut nearest = _mm_set1_epi8(len as i8);
+        // Going down so that the closest occurrence wins
+        for shift in (1..len).rev() {
+            let equal = _mm_cmpeq_epi8(block, load(&buf[pos - shift..]));
+            nearest = _mm_or_si128(
+                _mm_and_si128(equal, _mm_set1_epi8(shift as i8)),
+                _mm_andnot_si128(equal, nearest),
+            );
+        }
+        _mm_storeu_si128(distances.as_mut_ptr().cast(), nearest);
+    }
+    distances
+}
+
+fn load(bytes: &[u8]) -> __m128i {
+    assert!(bytes.len() >= MAX_LEN);
+    // SAFETY: the slice holds a full register and the load is unaligned
+    unsafe { _mm_loadu_si128(bytes.as_ptr().cast()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use proptest::{collection::vec, prelude::*};
+    use rstest::*;
+
+    use super::*;
+    use crate::find_unique_pattern_scalar;
+
+    #[rstest]
+    #[case(
+        "mjqjpqmgbljsphdztnvjfqwrcgsmlbmjqjpqmgbljsphdztnvjfqwrcgsmlb",
+        4,
+        Some(7)
+    )]
+    #[case(
+        "mjqjpqmgbljsphdztnvjfqwrcgsmlbmjqjpqmgbljsphdztnvjfqwrcgsmlb",
+        14,
+        Some(19)
+    )]
+    #[case(
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaabcdefghijklmnop",
+        16,
+        Some(68)
+    )]
+    #[case("abcabcabcabcabcabcabcabcabcabcabcabcabcabc", 4, None)]
+    fn test_find(#[case] buf: &str, #[case] len: usize, #[case] expected: Option<usize>) {
+        assert_eq!(find_unique_pattern(buf.as_bytes(), len), expected);
+    }
+
+    proptest! {
+        #[test]
+        fn matches_scalar(buf in vec(0u8..24, 0..300), len in 1..=MAX_LEN) {
+            prop_assert_eq!(
+                find_unique_pattern(&buf, len),
+                find_unique_pattern_scalar(&buf, len)
+            );
+        }
+
+        #[test]
+        fn matches_scalar_on_any_byte(buf in vec(any::<u8>(), 0..300), len in 1..=MAX_LEN) {
+            prop_assert_eq!(
+                find_unique_pattern(&buf, len),
+                find_unique_pattern_scalar(&buf, len)
+            );
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::{collections::BTreeMap, ops::Range};
+
+use super::*;
+
+/// Returns the end of every marker of the given length
+pub fn marker_positions(buf: &[u8], len: usize) -> Vec<usize> {
+    let mut window = Window::new(len);
+    buf.iter()
+        .enumerate()
+        .filter(|&(_, &byte)| window.push(byte))
+        .map(|(pos, _)| pos + 1)
+        .collect()
+}
+
+/// Returns the length of the longest all-distinct run ending at each byte
+pub fn distinct_runs(buf: &[u8]) -> impl Iterator<Item = usize> + '_ {
+    let mut last_seen = [None; 256];
+    let mut start = 0;
+    buf.iter().enumerate().map(move |(pos, &byte)| {
+        if let Some(seen) = last_seen[byte as usize] {
+            start = start.max(seen + 1);
+        }
+        last_seen[byte as usize] = Some(pos);
+        pos + 1 - start
+    })
+}
+
+/// Returns the first of the longest all-distinct runs
+pub fn longest_distinct_run(buf: &[u8]) -> Range<usize> {
+    distinct_runs(buf)
+        .enumerate()
+        .fold(0..0, |longest, (pos, len)| {
+            if len > longest.len() {
+                pos + 1 - len..pos + 1
+            } else {
+                longest
+            }
+        })
+}
+
+/// Counts the bytes by the length of the all-distinct run ending there
+pub fn histogram(buf: &[u8]) -> BTreeMap<usize, usize> {
+    let mut counts = BTreeMap::new();
+    for len in distinct_runs(buf) {
+        *counts.entry(len).or_default() += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    use super::*;
+
+    #[rstest]
+    #[case("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4, &(7..=30).collect::<Vec<_>>())]
+    #[case("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14, &[19, 25, 26, 27, 28, 29, 30])]
+    #[case("aaaa", 2, &[])]
+    fn test_marker_positions(#[case] buf: &str, #[case] len: usize, #[case] expected: &[usize]) {
+        assert_eq!(marker_positions(buf.as_bytes(), len), expected);
+    }
+
+    #[rstest]
+    #[case("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 12..30)]
+    #[case("abcabcd", 3..7)]
+    #[case("aaa", 0..1)]
+    #[case("", 0..0)]
+    fn test_longest_distinct_run(#[case] buf: &str, #[case] expected: Range<usize>) {
+        assert_eq!(longest_distinct_run(buf.as_bytes()), expected);
+    }
+
+    #[rstest]
+    fn test_histogram() {
+        assert_eq!(
+            histogram(b"abcabcdd"),
+            BTreeMap::from([(1, 2), (2, 1), (3, 4), (4, 1)])
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+
+use super::*;
+
+/// Every entry below the root, parents before their contents
+fn entries(tree: &DirectoryTree) -> impl Iterator<Item = Entry<'_>> {
+    tree.sized().entries().into_iter().skip(1)
+}
+
+/// Extension of a file name, ignoring the leading dot of hidden files
+pub fn extension(name: &str) -> Option<&str> {
+    match name.rsplit_once('.') {
+        Some(("", _)) | None => None,
+        Some((_, ext)) => Some(ext),
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    pub files: usize,
+    pub size: usize,
+}
+
+/// Number and total size of files by extension, `None` gathering files
+/// without one
+pub fn size_by_extension(tree: &DirectoryTree) -> BTreeMap<Option<&str>, Usage> {
+    let mut usage: BTreeMap<_, Usage> = BTreeMap::new();
+    for file in entries(tree).filter(|e| !e.is_dir()) {
+        let usage = usage.entry(extension(file.name())).or_default();
+        usage.files += 1;
+        usage.size += file.size;
+    }
+    usage
+}
+
+/// The `k` largest files with their paths, largest first
+pub fn largest_files(tree: &DirectoryTree, k: usize) -> Vec<(String, usize)> {
+    let mut files: Vec<_> = entries(tree)
+        .filter(|e| !e.is_dir())
+        .map(|e| (e.path, e.size))
+        .collect();
+    files.sort_by(|(p1, s1), (p2, s2)| s2.cmp(s1).then_with(|| p1.cmp(p2)));
+    files.truncate(k);
+    files
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DepthStats {
+    /// Depth of the deepest entry
+    pub max: usize,
+    pub mean_file_depth: f64,
+    /// Number of files and directories at each depth
+    pub entries: BTreeMap<usize, (usize, usize)>,
+}
+
+pub fn depth_stats(tree: &DirectoryTree) -> DepthStats {
+    let mut stats = DepthStats::default();
+    let (mut files, mut depths) = (0, 0);
+    for entry in entries(tree) {
+        stats.max = stats.max.max(entry.depth);
+        let (file_count, dir_count) = stats.entries.entry(entry.depth).or_default();
+        if entry.is_dir() {
+            *dir_count += 1;
+        } else {
+            *file_count += 1;
+            files += 1;
+            depths += entry.depth;
+        }
+    }
+    if files > 0 {
+        stats.mean_file_depth = depths as f64 / files as f64;
+    }
+    stats
+}
+
+/// Names used in more than one directory, with the paths using them
+pub fn duplicate_names(tree: &DirectoryTree) -> BTreeMap<&str, Vec<String>> {
+    let mut paths: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for entry in entries(tree) {
+        paths.entry(entry.name()).or_default().push(entry.path);
+    }
+    paths.retain(|_, paths| paths.len() > 1);
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    use super::*;
+    use crate::tests::{input, replay};
+
+    /// Example tree with a hidden file and a second `f` in `/d`
+    #[fixture]
+    fn tree(input: &str) -> DirectoryTree {
+        replay(&format!("{input}$ ls\n1 .txt\n3 f\n")).unwrap()
+    }
+
+    #[rstest]
+    #[case("b.txt", Some("txt"))]
+    #[case("archive.tar.gz", Some("gz"))]
+    #[case(".txt", None)]
+    #[case("f", None)]
+    fn test_extension(#[case] name: &str, #[case] expected: Option<&str>) {
+        assert_eq!(extension(name), expected);
+    }
+
+    #[rstest]
+    fn test_size_by_extension(tree: DirectoryTree) {
+        assert_eq!(
+            size_by_extension(&tree),
+            BTreeMap::from([
+                (
+                    None,
+                    Usage {
+                        files: 7,
+                        size: 29116 + 2557 + 584 + 4060174 + 7214296 + 1 + 3
+                    }
+                ),
+                (
+                    Some("dat"),
+                    Usage {
+                        files: 1,
+                        size: 8504156
+                    }
+                ),
+                (
+                    Some("ext"),
+                    Usage {
+                        files: 1,
+                        size: 5626152
+                    }
+                ),
+                (
+                    Some("log"),
+                    Usage {
+                        files: 1,
+                        size: 8033020
+                    }
+                ),
+                (
+                    Some("lst"),
+                    Usage {
+                        files: 1,
+                        size: 62596
+                    }
+                ),
+                (
+                    Some("txt"),
+                    Usage {
+                        files: 1,
+                        size: 14848514
+                    }
+                ),
+            ])
+        );
+    }
+
+    #[rstest]
+    fn test_largest_files(tree: DirectoryTree) {
+        assert_eq!(
+            largest_files(&tree, 3),
+            vec![
+                ("/b.txt".into(), 14848514),
+                ("/c.dat".into(), 8504156),
+                ("/d/d.log".into(), 8033020),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_depth_stats(tree: DirectoryTree) {
+        let stats = depth_stats(&tree);
+
+        assert_eq!(stats.max, 3);
+        assert_eq!(stats.mean_file_depth, 23.0 / 12.0);
+        assert_eq!(
+            stats.entries,
+            BTreeMap::from([(1, (2, 2)), (2, (9, 1)), (3, (1, 0))])
+        );
+    }
+
+    #[rstest]
+    fn test_duplicate_names(tree: DirectoryTree) {
+        assert_eq!(
+            duplicate_names(&tree),
+            BTreeMap::from([("f", vec!["/a/f".to_string(), "/d/f".to_string()])])
+        );
+    }
+}
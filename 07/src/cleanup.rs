@@ -0,0 +1,335 @@
+use std::fmt;
+
+use color_eyre::{eyre::eyre, Result};
+
+use super::*;
+
+/// Disk capacity and the free space an update requires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Disk {
+    pub capacity: usize,
+    pub required: usize,
+}
+
+impl Default for Disk {
+    fn default() -> Self {
+        Self {
+            capacity: 70_000_000,
+            required: 30_000_000,
+        }
+    }
+}
+
+impl Disk {
+    /// Space to free on a disk holding `used` bytes, if possible at all
+    fn needed(&self, used: usize) -> Option<usize> {
+        if self.required > self.capacity {
+            return None;
+        }
+        let total = self.required.checked_add(used)?;
+        Some(total.saturating_sub(self.capacity))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Plan {
+    /// There already is enough free space
+    NothingNeeded,
+    /// Delete a single directory, given with its size
+    Delete(String, usize),
+    /// Delete several directories, none of them inside another
+    DeleteAll(Vec<(String, usize)>),
+    /// Even deleting everything would not free enough space
+    Impossible,
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Plan::NothingNeeded => write!(f, "Nothing needs to be deleted"),
+            Plan::Delete(path, size) => write!(f, "Delete {path} to free {size}"),
+            Plan::DeleteAll(dirs) => {
+                let total: usize = dirs.iter().map(|(_, size)| size).sum();
+                write!(f, "Delete {} directories to free {total}:", dirs.len())?;
+                for (path, size) in dirs {
+                    write!(f, "\n  {path} ({size})")?;
+                }
+                Ok(())
+            }
+            Plan::Impossible => write!(f, "Not enough space can be freed"),
+        }
+    }
+}
+
+/// Paths and sizes of all directories
+fn directories(tree: &DirectoryTree) -> Vec<(String, usize)> {
+    render::Du(tree).entries()
+}
+
+/// Finds the smallest directory freeing enough space
+pub fn plan(tree: &DirectoryTree, disk: &Disk) -> Plan {
+    let needed = match disk.needed(tree.size()) {
+        None => return Plan::Impossible,
+        Some(0) => return Plan::NothingNeeded,
+        Some(needed) => needed,
+    };
+    directories(tree)
+        .into_iter()
+        .filter(|(_, size)| *size >= needed)
+        .min_by_key(|(_, size)| *size)
+        .map_or(Plan::Impossible, |(path, size)| Plan::Delete(path, size))
+}
+
+/// Finds at most `max_dirs` non-nested directories freeing enough space while
+/// deleting as little as possible, preferring fewer directories on ties
+///
+/// Directories are tried largest first, pruning any selection deleting more
+/// than the best one found so far, which starts as the best single directory,
+/// and any selection whose remaining slots cannot free enough space even with
+/// the largest remaining directories. Gives up after exploring `max_states`
+/// selections.
+pub fn plan_minimal(
+    tree: &DirectoryTree,
+    disk: &Disk,
+    max_dirs: usize,
+    max_states: usize,
+) -> Result<Plan> {
+    if max_dirs == 0 {
+        return Err(eyre!("At least one directory must be allowed for deletion"));
+    }
+    let (needed, single) = match plan(tree, disk) {
+        Plan::Delete(path, size) => (disk.needed(tree.size()).unwrap_or_default(), (path, size)),
+        plan => return Ok(plan),
+    };
+
+    let dirs = directories(tree);
+    let mut search = Search {
+        dirs: &dirs,
+        components: dirs.iter().map(|(path, _)| components(path)).collect(),
+        largest: dirs
+            .iter()
+            .scan(0, |total, (_, size)| {
+                *total += *size as u128;
+                Some(*total)
+            })
+            .collect(),
+        needed,
+        max_dirs,
+        max_states,
+        states: 0,
+        chosen: vec![],
+        best: vec![dirs
+            .iter()
+            .position(|d| *d == single)
+            .expect("Listed directory")],
+        best_size: single.1,
+    };
+    search.run(0, 0)?;
+
+    Ok(Plan::DeleteAll(
+        search.best.into_iter().map(|i| dirs[i].clone()).collect(),
+    ))
+}
+
+/// Depth-first search over selections of directories sorted by size, largest
+/// first
+struct Search<'a> {
+    dirs: &'a [(String, usize)],
+    components: Vec<Vec<&'a str>>,
+    /// Total size of the directories up to each index, which counts nested
+    /// directories repeatedly and so may not fit in a `usize`
+    largest: Vec<u128>,
+    needed: usize,
+    max_dirs: usize,
+    max_states: usize,
+    states: usize,
+    chosen: Vec<usize>,
+    best: Vec<usize>,
+    best_size: usize,
+}
+
+impl Search<'_> {
+    /// Upper bound of the space freed by adding up to `slots` directories
+    /// from `start` on
+    fn reachable(&self, start: usize, slots: usize) -> u128 {
+        let end = (start + slots).min(self.dirs.len());
+        let before = start.checked_sub(1).map_or(0, |i| self.largest[i]);
+        self.largest[end - 1] - before
+    }
+
+    fn run(&mut self, start: usize, size: usize) -> Result<()> {
+        for i in start..self.dirs.len() {
+            // Any further selection frees at least `needed`
+            if (self.needed, self.chosen.len() + 1) >= (self.best_size, self.best.len()) {
+                break;
+            }
+            let slots = self.max_dirs - self.chosen.len();
+            if size as u128 + self.reachable(i, slots) < self.needed as u128 {
+                break;
+            }
+            // Only nested selections can overflow, and those are skipped below
+            let Some(total) = size.checked_add(self.dirs[i].1) else {
+                continue;
+            };
+            let count = self.chosen.len() + 1;
+            if (total, count) >= (self.best_size, self.best.len()) {
+                continue;
+            }
+            let path = &self.components[i];
+            if self.chosen.iter().any(|&c| {
+                is_nested(&self.components[c], path) || is_nested(path, &self.components[c])
+            }) {
+                continue;
+            }
+
+            self.states += 1;
+            if self.states > self.max_states {
+                return Err(eyre!(
+                    "Gave up after exploring {} selections",
+                    self.max_states
+                ));
+            }
+            self.chosen.push(i);
+            if total >= self.needed {
+                self.best = self.chosen.clone();
+                self.best_size = total;
+            } else if count < self.max_dirs {
+                self.run(i + 1, total)?;
+            }
+            self.chosen.pop();
+        }
+        Ok(())
+    }
+}
+
+/// Names of the directories along an absolute path
+fn components(path: &str) -> Vec<&str> {
+    path.split('/').filter(|c| !c.is_empty()).collect()
+}
+
+/// Whether `inner` is below the directory `outer`
+fn is_nested(outer: &[&str], inner: &[&str]) -> bool {
+    inner.len() > outer.len() && inner.starts_with(outer)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    use super::*;
+    use crate::tests::{replay, tree};
+
+    // The example uses 48381165, leaving 21618835 free on a disk of 70000000
+    #[rstest]
+    #[case(70_000_000, 21_618_835, Plan::NothingNeeded)]
+    #[case(70_000_000, 21_619_000, Plan::Delete("/a/e".into(), 584))]
+    #[case(70_000_000, 21_620_000, Plan::Delete("/a".into(), 94853))]
+    #[case(70_000_000, 30_000_000, Plan::Delete("/d".into(), 24933642))]
+    #[case(70_000_000, 50_000_000, Plan::Delete("/".into(), 48381165))]
+    #[case(70_000_000, 70_000_001, Plan::Impossible)]
+    #[case(40_000_000, 100, Plan::Delete("/d".into(), 24933642))]
+    fn test_plan(
+        tree: DirectoryTree,
+        #[case] capacity: usize,
+        #[case] required: usize,
+        #[case] expected: Plan,
+    ) {
+        assert_eq!(plan(&tree, &Disk { capacity, required }), expected);
+    }
+
+    #[rstest]
+    #[case(
+        70_000_000,
+        46_618_835,
+        Plan::DeleteAll(vec![("/d".into(), 24933642), ("/a".into(), 94853)])
+    )]
+    #[case(
+        70_000_000,
+        30_000_000,
+        Plan::DeleteAll(vec![("/d".into(), 24933642)])
+    )]
+    #[case(70_000_000, 21_618_835, Plan::NothingNeeded)]
+    #[case(70_000_000, 70_000_001, Plan::Impossible)]
+    fn test_plan_minimal(
+        tree: DirectoryTree,
+        #[case] capacity: usize,
+        #[case] required: usize,
+        #[case] expected: Plan,
+    ) {
+        assert_eq!(
+            plan_minimal(&tree, &Disk { capacity, required }, 3, 1000).unwrap(),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn test_plan_minimal_no_dirs(tree: DirectoryTree) {
+        assert_eq!(
+            plan_minimal(&tree, &Disk::default(), 0, 1000)
+                .unwrap_err()
+                .to_string(),
+            "At least one directory must be allowed for deletion"
+        );
+    }
+
+    #[rstest]
+    fn test_used_overflow() {
+        let tree = replay(&format!("$ ls\n{} x\n", usize::MAX)).unwrap();
+        let disk = Disk {
+            capacity: 200,
+            required: 150,
+        };
+
+        assert_eq!(disk.needed(usize::MAX), None);
+        assert_eq!(plan(&tree, &disk), Plan::Impossible);
+        assert_eq!(
+            plan_minimal(&tree, &disk, 3, 1000).unwrap(),
+            Plan::Impossible
+        );
+    }
+
+    #[rstest]
+    fn test_plan_minimal_huge_dirs() {
+        let half = usize::MAX / 2 - 100;
+        let tree = replay(&format!(
+            "$ cd /\n$ ls\ndir a\ndir b\ndir c\n\
+             $ cd a\n$ ls\n{half} x\n\
+             $ cd ../b\n$ ls\n{half} y\n\
+             $ cd ../c\n$ ls\n50 z\n"
+        ))
+        .unwrap();
+        let disk = Disk {
+            capacity: 200,
+            required: 150,
+        };
+
+        assert_eq!(
+            plan_minimal(&tree, &disk, 3, 1000).unwrap(),
+            Plan::DeleteAll(vec![("/a".into(), half), ("/b".into(), half)])
+        );
+    }
+
+    #[rstest]
+    fn test_plan_minimal_state_limit(tree: DirectoryTree) {
+        let disk = Disk {
+            capacity: 70_000_000,
+            required: 46_618_835,
+        };
+
+        assert_eq!(
+            plan_minimal(&tree, &disk, 3, 1).unwrap_err().to_string(),
+            "Gave up after exploring 1 selections"
+        );
+    }
+
+    #[rstest]
+    #[case("/", "/a", true)]
+    #[case("/a", "/a/b", true)]
+    #[case("/a", "/ab", false)]
+    #[case("/a", "/a", false)]
+    #[case("/a/b", "/a", false)]
+    fn test_is_nested(#[case] outer: &str, #[case] inner: &str, #[case] expected: bool) {
+        assert_eq!(is_nested(&components(outer), &components(inner)), expected);
+    }
+}
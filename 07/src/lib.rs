@@ -0,0 +1,825 @@
+use std::{mem, slice, str::FromStr};
+
+use color_eyre::{
+    eyre::{eyre, Context},
+    Report, Result,
+};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{alpha1, char, digit1, line_ending, not_line_ending, space0, space1},
+    combinator::{eof, map, map_res, not, opt, recognize, verify},
+    multi::many0,
+    sequence::{delimited, pair, preceded, separated_pair, terminated},
+    IResult,
+};
+
+pub mod analytics;
+pub mod cleanup;
+pub mod render;
+pub mod vfs;
+
+pub fn part1(dt: &DirectoryTree) -> usize {
+    dt.iter_dirs()
+        .map(|d| d.size())
+        .filter(|size| *size <= 100_000)
+        .sum()
+}
+
+pub fn part2(dt: &DirectoryTree) -> cleanup::Plan {
+    cleanup::plan(dt, &cleanup::Disk::default())
+}
+
+#[derive(Debug)]
+pub enum DirectoryTree {
+    File(String, usize),
+    Directory(String, Vec<Self>),
+}
+
+impl DirectoryTree {
+    pub fn size(&self) -> usize {
+        match self {
+            DirectoryTree::File(_, s) => *s,
+            DirectoryTree::Directory(_, contents) => contents.iter().map(|e| e.size()).sum(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            DirectoryTree::File(n, _) => n,
+            DirectoryTree::Directory(n, _) => n,
+        }
+    }
+
+    pub fn iter_dirs(&self) -> impl Iterator<Item = &'_ Self> {
+        DirectoryTreeIter {
+            children: slice::from_ref(self),
+            parent: None,
+        }
+    }
+
+    /// Annotates the tree with the size of every entry
+    pub fn sized(&self) -> SizedTree<'_> {
+        match self {
+            DirectoryTree::File(_, size) => SizedTree {
+                tree: self,
+                size: *size,
+                contents: vec![],
+            },
+            DirectoryTree::Directory(_, contents) => {
+                let contents: Vec<_> = contents.iter().map(Self::sized).collect();
+                SizedTree {
+                    tree: self,
+                    size: contents.iter().map(|c| c.size).sum(),
+                    contents,
+                }
+            }
+        }
+    }
+}
+
+/// Directory tree along with the total size of every entry, computed
+/// bottom-up in a single pass
+#[derive(Debug)]
+pub struct SizedTree<'a> {
+    pub tree: &'a DirectoryTree,
+    pub size: usize,
+    pub contents: Vec<Self>,
+}
+
+/// Entry of a [`SizedTree`] with its location
+#[derive(Debug, Clone)]
+pub struct Entry<'a> {
+    pub tree: &'a DirectoryTree,
+    pub path: String,
+    /// Number of directories above the entry, the root being at depth 0
+    pub depth: usize,
+    pub size: usize,
+}
+
+impl<'a> Entry<'a> {
+    pub fn name(&self) -> &'a str {
+        self.tree.name()
+    }
+
+    pub fn is_dir(&self) -> bool {
+        matches!(self.tree, DirectoryTree::Directory(..))
+    }
+}
+
+impl<'a> SizedTree<'a> {
+    pub fn name(&self) -> &'a str {
+        self.tree.name()
+    }
+
+    pub fn is_dir(&self) -> bool {
+        matches!(self.tree, DirectoryTree::Directory(..))
+    }
+
+    /// The tree and everything below it, parents before their contents, the
+    /// tree itself being at `/`
+    pub fn entries(&self) -> Vec<Entry<'a>> {
+        fn walk<'a>(
+            tree: &SizedTree<'a>,
+            path: String,
+            depth: usize,
+            entries: &mut Vec<Entry<'a>>,
+        ) {
+            entries.push(Entry {
+                tree: tree.tree,
+                path: path.clone(),
+                depth,
+                size: tree.size,
+            });
+            for entry in &tree.contents {
+                walk(entry, render::join(&path, entry.name()), depth + 1, entries);
+            }
+        }
+
+        let mut entries = vec![];
+        walk(self, "/".into(), 0, &mut entries);
+        entries
+    }
+}
+
+#[derive(Default, Debug)]
+struct DirectoryTreeIter<'a> {
+    children: &'a [DirectoryTree],
+    parent: Option<Box<Self>>,
+}
+
+impl<'a> Iterator for DirectoryTreeIter<'a> {
+    type Item = &'a DirectoryTree;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.children.first() {
+            None => match self.parent.take() {
+                Some(parent) => {
+                    *self = *parent;
+                    self.next()
+                }
+                None => None,
+            },
+            Some(DirectoryTree::File(_, _)) => {
+                self.children = &self.children[1..];
+                self.next()
+            }
+            Some(d @ DirectoryTree::Directory(_, subdirs)) => {
+                self.children = &self.children[1..];
+                *self = Self {
+                    children: subdirs.as_slice(),
+                    parent: Some(Box::new(mem::take(self))),
+                };
+                Some(d)
+            }
+        }
+    }
+}
+
+/// Replays the commands onto a [`vfs::FileSystem`] and copies the result
+impl TryFrom<Vec<terminal::Command>> for DirectoryTree {
+    type Error = Report;
+
+    fn try_from(commands: Vec<terminal::Command>) -> Result<Self, Self::Error> {
+        Self::try_from(&vfs::FileSystem::try_from(commands)?)
+    }
+}
+
+pub mod terminal {
+    use std::fmt;
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Path {
+        Root,
+        Current,
+        Parent,
+        Subdir(String),
+        /// Path of several segments, e.g. `/a/b` or `../c`
+        Multi(Vec<Path>),
+    }
+
+    impl From<&str> for Path {
+        fn from(s: &str) -> Self {
+            let mut segments = vec![];
+            if s.starts_with('/') {
+                segments.push(Path::Root);
+            }
+            for segment in s.split('/') {
+                match segment {
+                    "" | "." => {}
+                    ".." => segments.push(Path::Parent),
+                    name => segments.push(Path::Subdir(name.into())),
+                }
+            }
+            match segments.len() {
+                0 => Path::Current,
+                1 => segments.pop().unwrap(),
+                _ => Path::Multi(segments),
+            }
+        }
+    }
+
+    impl fmt::Display for Path {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Path::Root => write!(f, "/"),
+                Path::Current => write!(f, "."),
+                Path::Parent => write!(f, ".."),
+                Path::Subdir(name) => write!(f, "{name}"),
+                Path::Multi(segments) => {
+                    let (root, segments) = match segments.split_first() {
+                        Some((Path::Root, rest)) => ("/", rest),
+                        _ => ("", segments.as_slice()),
+                    };
+                    write!(f, "{root}")?;
+                    for (i, segment) in segments.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, "/")?;
+                        }
+                        write!(f, "{segment}")?;
+                    }
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum Command {
+        Cd(Path),
+        Ls(Vec<DirectoryEntry>),
+        Mkdir {
+            path: Path,
+            /// `-p`, also creating missing parents
+            parents: bool,
+        },
+        Rm {
+            path: Path,
+            /// `-r`, allowing directories to be removed
+            recursive: bool,
+            /// `-f`, ignoring a missing path
+            force: bool,
+        },
+        /// Disk usage query, whose output is ignored
+        Du(Option<Path>),
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum DirectoryEntry {
+        Dir(String),
+        File(String, usize),
+    }
+
+    /// What to do with commands the parser does not know about
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub enum UnknownCommands {
+        #[default]
+        Reject,
+        /// Skip them along with their output, with a warning
+        Skip,
+    }
+
+    impl FromStr for UnknownCommands {
+        type Err = Report;
+
+        fn from_str(s: &str) -> Result<Self> {
+            match s {
+                "reject" => Ok(Self::Reject),
+                "skip" => Ok(Self::Skip),
+                _ => Err(eyre!("Unknown policy {s:?}, expected reject or skip")),
+            }
+        }
+    }
+
+    /// Unknown command skipped while parsing
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct Warning {
+        pub line: usize,
+        pub command: String,
+    }
+
+    impl fmt::Display for Warning {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "Skipped unknown command '{}' at line {}",
+                self.command, self.line
+            )
+        }
+    }
+
+    const COMMANDS: [&str; 5] = ["cd", "ls", "mkdir", "rm", "du"];
+
+    fn path(input: &str) -> IResult<&str, Path> {
+        map(verify(not_line_ending, |p: &str| !p.is_empty()), Path::from)(input)
+    }
+
+    fn entry(input: &str) -> IResult<&str, DirectoryEntry> {
+        alt((
+            preceded(
+                tag("dir "),
+                map(not_line_ending, |d: &str| DirectoryEntry::Dir(d.into())),
+            ),
+            map_res(
+                separated_pair(digit1, space1, not_line_ending),
+                |(size, name): (&str, &str)| -> Result<DirectoryEntry, Report> {
+                    Ok(DirectoryEntry::File(
+                        name.into(),
+                        size.parse().wrap_err("Invalid size")?,
+                    ))
+                },
+            ),
+        ))(input)
+    }
+
+    /// Entries listed by `ls`, skipping blank lines between them
+    fn listing(input: &str) -> IResult<&str, Vec<DirectoryEntry>> {
+        many0(delimited(many0(blank_line), entry, end_of_command))(input)
+    }
+
+    /// Lines up to the next command
+    fn output(input: &str) -> IResult<&str, ()> {
+        map(
+            many0(preceded(
+                not(tag("$ ")),
+                alt((
+                    terminated(not_line_ending, line_ending),
+                    verify(not_line_ending, |line: &str| !line.is_empty()),
+                )),
+            )),
+            |_| (),
+        )(input)
+    }
+
+    /// Command options such as `-rf`, as the letters of all of them
+    fn flags(input: &str) -> IResult<&str, String> {
+        map(
+            many0(preceded(pair(space1, char('-')), alpha1)),
+            |flags: Vec<&str>| flags.concat(),
+        )(input)
+    }
+
+    /// Options limited to the letters in `supported`
+    fn supported_flags(supported: &str) -> impl FnMut(&str) -> IResult<&str, String> + '_ {
+        move |input: &str| verify(flags, |f: &str| f.chars().all(|c| supported.contains(c)))(input)
+    }
+
+    fn end_of_command(input: &str) -> IResult<&str, &str> {
+        alt((line_ending, eof))(input)
+    }
+
+    fn cd(input: &str) -> IResult<&str, Command> {
+        map(
+            delimited(terminated(tag("cd"), space1), path, end_of_command),
+            Command::Cd,
+        )(input)
+    }
+
+    fn ls(input: &str) -> IResult<&str, Command> {
+        map(
+            preceded(terminated(tag("ls"), end_of_command), listing),
+            Command::Ls,
+        )(input)
+    }
+
+    fn mkdir(input: &str) -> IResult<&str, Command> {
+        map(
+            delimited(
+                tag("mkdir"),
+                pair(supported_flags("p"), preceded(space1, path)),
+                end_of_command,
+            ),
+            |(flags, path)| Command::Mkdir {
+                path,
+                parents: flags.contains('p'),
+            },
+        )(input)
+    }
+
+    fn rm(input: &str) -> IResult<&str, Command> {
+        map(
+            delimited(
+                tag("rm"),
+                pair(supported_flags("rRf"), preceded(space1, path)),
+                end_of_command,
+            ),
+            |(flags, path)| Command::Rm {
+                path,
+                recursive: flags.contains(['r', 'R']),
+                force: flags.contains('f'),
+            },
+        )(input)
+    }
+
+    fn du(input: &str) -> IResult<&str, Command> {
+        map(
+            delimited(
+                pair(tag("du"), flags),
+                opt(preceded(space1, path)),
+                pair(end_of_command, output),
+            ),
+            Command::Du,
+        )(input)
+    }
+
+    /// Line of only whitespace, which may be the last one without a line
+    /// ending
+    fn blank_line(input: &str) -> IResult<&str, &str> {
+        verify(
+            recognize(pair(space0, alt((line_ending, eof)))),
+            |line: &str| !line.is_empty(),
+        )(input)
+    }
+
+    pub fn parse(i: &str) -> Result<Vec<Command>> {
+        parse_with(i, UnknownCommands::Reject).map(|(commands, _)| commands)
+    }
+
+    /// Parses a terminal log, also returning the unknown commands skipped
+    pub fn parse_with(i: &str, unknown: UnknownCommands) -> Result<(Vec<Command>, Vec<Warning>)> {
+        let mut commands = vec![];
+        let mut warnings = vec![];
+        let mut rest = i;
+        let mut line = 1;
+        while !rest.is_empty() {
+            if let Ok((r, _)) = blank_line(rest) {
+                line += newlines(rest, r);
+                rest = r;
+                continue;
+            }
+            let text = rest.lines().next().unwrap_or_default();
+            let Some(command_line) = text.strip_prefix("$ ") else {
+                return Err(eyre!("Unexpected output {text:?} at line {line}"));
+            };
+            if let Ok((r, command)) = preceded(tag("$ "), alt((cd, ls, mkdir, rm, du)))(rest) {
+                commands.push(command);
+                line += newlines(rest, r);
+                rest = r;
+                continue;
+            }
+
+            let name = command_line.split_whitespace().next().unwrap_or_default();
+            if COMMANDS.contains(&name) {
+                return Err(eyre!("Invalid '{name}' command {text:?} at line {line}"));
+            }
+            match unknown {
+                UnknownCommands::Reject => {
+                    return Err(eyre!("Unknown command '{name}' at line {line}"));
+                }
+                UnknownCommands::Skip => {
+                    warnings.push(Warning {
+                        line,
+                        command: name.into(),
+                    });
+                    let (r, _) = preceded(
+                        terminated(not_line_ending, alt((line_ending, eof))),
+                        output,
+                    )(rest)
+                    .map_err(|err| eyre!("Could not parse input, {err}"))?;
+                    line += newlines(rest, r);
+                    rest = r;
+                }
+            }
+        }
+        Ok((commands, warnings))
+    }
+
+    /// Number of lines consumed going from `input` to its suffix `rest`
+    fn newlines(input: &str, rest: &str) -> usize {
+        input[..input.len() - rest.len()].matches('\n').count()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use nom::combinator::all_consuming;
+        use pretty_assertions::assert_eq;
+        use rstest::*;
+
+        use super::*;
+
+        #[rstest]
+        #[case("cd /\n", Command::Cd(Path::Root))]
+        #[case("cd ..\n", Command::Cd(Path::Parent))]
+        #[case("cd abcd\n", Command::Cd(Path::Subdir("abcd".into())))]
+        #[case("cd /", Command::Cd(Path::Root))]
+        fn test_cd(#[case] input: &str, #[case] expected: Command) {
+            assert_eq!(all_consuming(cd)(input).unwrap().1, expected);
+        }
+
+        #[rstest]
+        #[case("dir abcd")]
+        #[case("123 abcd")]
+        #[case("123 abcd\ndir efg")]
+        #[case("123 abcd\n\n  \ndir efg\n")]
+        fn test_listing(#[case] input: &str) {
+            all_consuming(listing)(input).unwrap();
+        }
+        #[rstest]
+        #[case("ls\n")]
+        #[case("ls\ndir abcd\n")]
+        #[case("ls\n123 abcd\n")]
+        #[case("ls\n123 abc.d\ndir efg\n")]
+        #[case("ls")]
+        fn test_ls(#[case] input: &str) {
+            all_consuming(ls)(input).unwrap();
+        }
+
+        #[rstest]
+        #[case("/", Path::Root)]
+        #[case("a/", Path::Subdir("a".into()))]
+        #[case("/a/b", Path::Multi(vec![Path::Root, Path::Subdir("a".into()), Path::Subdir("b".into())]))]
+        #[case("../c", Path::Multi(vec![Path::Parent, Path::Subdir("c".into())]))]
+        #[case(".", Path::Current)]
+        #[case("./", Path::Current)]
+        #[case("./a/./b/.", Path::Multi(vec![Path::Subdir("a".into()), Path::Subdir("b".into())]))]
+        #[case("/./a", Path::Multi(vec![Path::Root, Path::Subdir("a".into())]))]
+        fn test_path(#[case] input: &str, #[case] expected: Path) {
+            assert_eq!(Path::from(input), expected);
+        }
+
+        #[rstest]
+        #[case("/a/b")]
+        #[case("../c")]
+        #[case("..")]
+        #[case(".")]
+        fn test_path_display(#[case] input: &str) {
+            assert_eq!(Path::from(input).to_string(), input);
+        }
+
+        #[rstest]
+        #[case("mkdir x\n", Command::Mkdir { path: Path::Subdir("x".into()), parents: false })]
+        #[case("mkdir -p /x/y\n", Command::Mkdir { path: Path::from("/x/y"), parents: true })]
+        #[case("rm x\n", Command::Rm { path: Path::Subdir("x".into()), recursive: false, force: false })]
+        #[case("rm -rf x", Command::Rm { path: Path::Subdir("x".into()), recursive: true, force: true })]
+        #[case("rm -R -f x", Command::Rm { path: Path::Subdir("x".into()), recursive: true, force: true })]
+        #[case("du\n", Command::Du(None))]
+        #[case("du -sh\n", Command::Du(None))]
+        #[case("du -sh a\n123\ta\n", Command::Du(Some(Path::Subdir("a".into()))))]
+        fn test_commands(#[case] input: &str, #[case] expected: Command) {
+            assert_eq!(
+                all_consuming(alt((mkdir, rm, du)))(input).unwrap().1,
+                expected
+            );
+        }
+
+        #[rstest]
+        fn test_skip_unknown() {
+            let input = "$ cd /\n\n$ pwd\n/\n$ ls\n1 a\n  \n$ git status\n$ du\n12 .\n";
+            let (commands, warnings) = parse_with(input, UnknownCommands::Skip).unwrap();
+
+            assert_eq!(
+                commands,
+                vec![
+                    Command::Cd(Path::Root),
+                    Command::Ls(vec![DirectoryEntry::File("a".into(), 1)]),
+                    Command::Du(None),
+                ]
+            );
+            assert_eq!(
+                warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                vec![
+                    "Skipped unknown command 'pwd' at line 3",
+                    "Skipped unknown command 'git' at line 8",
+                ]
+            );
+        }
+
+        #[rstest]
+        #[case("$ ls\n1 x\n\n2 y\n", vec![Command::Ls(vec![DirectoryEntry::File("x".into(), 1), DirectoryEntry::File("y".into(), 2)])])]
+        #[case("$ ls\n1 x\n  ", vec![Command::Ls(vec![DirectoryEntry::File("x".into(), 1)])])]
+        #[case("$ ls\n1 x\n\n$ cd /\n  \n", vec![Command::Ls(vec![DirectoryEntry::File("x".into(), 1)]), Command::Cd(Path::Root)])]
+        fn test_blank_lines(#[case] input: &str, #[case] expected: Vec<Command>) {
+            assert_eq!(parse(input).unwrap(), expected);
+        }
+
+        #[rstest]
+        #[case("$ ls\n1 x\n\nfoo\n", "Unexpected output \"foo\" at line 4")]
+        #[case("$ cd /\n$ pwd\n", "Unknown command 'pwd' at line 2")]
+        #[case("$ cd /\n\n$ cd\n", "Invalid 'cd' command \"$ cd\" at line 3")]
+        #[case("$ ls\nfoo\n", "Unexpected output \"foo\" at line 2")]
+        #[case("$ cd /\n$ rm -rf\n", "Invalid 'rm' command \"$ rm -rf\" at line 2")]
+        #[case("$ rm -i x\n", "Invalid 'rm' command \"$ rm -i x\" at line 1")]
+        #[case("$ mkdir -m x\n", "Invalid 'mkdir' command \"$ mkdir -m x\" at line 1")]
+        #[case("$ mkdir\n", "Invalid 'mkdir' command \"$ mkdir\" at line 1")]
+        fn test_reject(#[case] input: &str, #[case] expected: &str) {
+            assert_eq!(parse(input).unwrap_err().to_string(), expected);
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    use super::*;
+
+    #[fixture]
+    pub(crate) fn input() -> &'static str {
+        indoc! {"
+            $ cd /
+            $ ls
+            dir a
+            14848514 b.txt
+            8504156 c.dat
+            dir d
+            $ cd a
+            $ ls
+            dir e
+            29116 f
+            2557 g
+            62596 h.lst
+            $ cd e
+            $ ls
+            584 i
+            $ cd ..
+            $ cd ..
+            $ cd d
+            $ ls
+            4060174 j
+            8033020 d.log
+            5626152 d.ext
+            7214296 k
+        "}
+    }
+
+    pub(crate) fn replay(input: &str) -> Result<DirectoryTree> {
+        terminal::parse(input)?.try_into()
+    }
+
+    /// Directory tree of the example terminal output
+    #[fixture]
+    pub(crate) fn tree(input: &str) -> DirectoryTree {
+        replay(input).unwrap()
+    }
+
+    #[rstest]
+    fn test_terminal(input: &str) {
+        let commands = terminal::parse(input).unwrap();
+        let fs: DirectoryTree = commands.try_into().unwrap();
+
+        assert_eq!(fs.size(), 48381165);
+
+        assert_eq!(part1(&fs), 95437);
+        assert_eq!(part2(&fs), cleanup::Plan::Delete("/d".into(), 24933642));
+    }
+
+    #[rstest]
+    fn test_sized_entries(tree: DirectoryTree) {
+        let entries = tree.sized().entries();
+
+        assert_eq!(entries.len(), 14);
+        assert_eq!(
+            entries
+                .iter()
+                .filter(|e| e.is_dir())
+                .map(|e| (e.path.as_str(), e.depth, e.size))
+                .collect::<Vec<_>>(),
+            [
+                ("/", 0, 48381165),
+                ("/a", 1, 94853),
+                ("/a/e", 2, 584),
+                ("/d", 1, 24933642)
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_extended_commands() {
+        let input = indoc! {"
+            $ cd /
+            $ mkdir a
+            $ cd a
+            $ ls
+            dir b
+            $ cd b/../b
+            $ ls
+            10 x
+            $ cd ..
+            $ mkdir c
+            $ rm -r c
+            $ du -sh
+            10 .
+            $ cd ..
+            $ ls
+            5 y
+        "};
+        let fs: DirectoryTree = terminal::parse(input).unwrap().try_into().unwrap();
+
+        assert_eq!(fs.size(), 15);
+        assert_eq!(
+            fs.iter_dirs().map(DirectoryTree::name).collect::<Vec<_>>(),
+            ["/", "a", "b"]
+        );
+    }
+
+    #[rstest]
+    fn test_cd_root() {
+        let fs = replay(indoc! {"
+            $ cd /
+            $ ls
+            dir a
+            $ cd a
+            $ ls
+            dir b
+            $ cd b
+            $ cd /
+            $ ls
+            dir a
+            7 c
+        "})
+        .unwrap();
+
+        assert_eq!(fs.size(), 7);
+        assert_eq!(fs.iter_dirs().count(), 3);
+    }
+
+    #[rstest]
+    fn test_repeated_ls() {
+        let fs = replay(indoc! {"
+            $ cd /
+            $ ls
+            dir a
+            10 b
+            $ cd a
+            $ ls
+            5 c
+            $ cd ..
+            $ ls
+            dir a
+            10 b
+        "})
+        .unwrap();
+
+        assert_eq!(fs.size(), 15);
+        assert_eq!(
+            fs.iter_dirs().map(DirectoryTree::size).collect::<Vec<_>>(),
+            [15, 5]
+        );
+    }
+
+    #[rstest]
+    fn test_rm_and_mkdir_paths() {
+        let fs = replay(indoc! {"
+            $ ls
+            dir a
+            dir b
+            $ cd b
+            $ mkdir /a/c
+            $ cd /a/c
+            $ ls
+            3 d
+            $ rm -r /a/../b
+            $ cd ..
+            $ ls
+            4 e
+        "})
+        .unwrap();
+
+        assert_eq!(
+            fs.iter_dirs().map(DirectoryTree::name).collect::<Vec<_>>(),
+            ["/", "a", "c"]
+        );
+        assert_eq!(fs.size(), 7);
+    }
+
+    #[rstest]
+    fn test_cd_current() {
+        let fs = replay(indoc! {"
+            $ cd /
+            $ ls
+            dir a
+            $ cd .
+            $ cd ./a/.
+            $ cd ./
+            $ ls
+            3 b
+            $ cd ./../a/./..
+            $ ls
+            4 c
+        "})
+        .unwrap();
+
+        assert_eq!(
+            fs.iter_dirs().map(DirectoryTree::size).collect::<Vec<_>>(),
+            [7, 3]
+        );
+    }
+
+    #[rstest]
+    #[case(
+        "$ ls\n1 a\n$ ls\ndir a\n",
+        "'a' listed both as a file and as a directory"
+    )]
+    #[case("$ cd a\n", "No such file or directory 'a'")]
+    #[case("$ ls\n1 a\n$ cd a\n", "Not a directory 'a'")]
+    #[case("$ rm /a/b\n", "No such file or directory '/a/b'")]
+    #[case("$ rm -r ..\n", "Cannot remove the root directory")]
+    #[case("$ ls\n1 a\n$ mkdir a\n", "'a' already exists in '/'")]
+    fn test_replay_errors(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(replay(input).unwrap_err().to_string(), expected);
+    }
+}
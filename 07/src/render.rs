@@ -0,0 +1,198 @@
+use std::fmt;
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use super::*;
+
+/// `tree`-like view of a directory tree, with the size of every entry
+pub struct Tree<'a>(pub &'a DirectoryTree);
+
+impl fmt::Display for Tree<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn entries(
+            f: &mut fmt::Formatter<'_>,
+            contents: &[SizedTree],
+            prefix: &str,
+        ) -> fmt::Result {
+            for (i, entry) in contents.iter().enumerate() {
+                let last = i + 1 == contents.len();
+                let (branch, indent) = if last {
+                    ("└── ", "    ")
+                } else {
+                    ("├── ", "│   ")
+                };
+                writeln!(f, "{prefix}{branch}{} ({})", entry.name(), entry.size)?;
+                entries(f, &entry.contents, &format!("{prefix}{indent}"))?;
+            }
+            Ok(())
+        }
+
+        let tree = self.0.sized();
+        writeln!(f, "{} ({})", tree.name(), tree.size)?;
+        entries(f, &tree.contents, "")
+    }
+}
+
+/// `du -h`-like summary of every directory, largest first
+pub struct Du<'a>(pub &'a DirectoryTree);
+
+impl Du<'_> {
+    /// Paths and sizes of all directories, largest first
+    pub fn entries(&self) -> Vec<(String, usize)> {
+        let mut entries: Vec<_> = self
+            .0
+            .sized()
+            .entries()
+            .into_iter()
+            .filter(Entry::is_dir)
+            .map(|e| (e.path, e.size))
+            .collect();
+        entries.sort_by(|(p1, s1), (p2, s2)| s2.cmp(s1).then_with(|| p1.cmp(p2)));
+        entries
+    }
+}
+
+impl fmt::Display for Du<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (path, size) in self.entries() {
+            writeln!(f, "{}\t{path}", human(size))?;
+        }
+        Ok(())
+    }
+}
+
+/// Appends a name to a directory path
+pub fn join(dir: &str, name: &str) -> String {
+    if dir.ends_with('/') {
+        format!("{dir}{name}")
+    } else {
+        format!("{dir}/{name}")
+    }
+}
+
+/// Formats a size like `du -h`, in the largest unit keeping the value below
+/// 1024, rounded up to one decimal below 10 and to a whole number otherwise
+pub fn human(size: usize) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+
+    if size < 1024 {
+        return size.to_string();
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    loop {
+        let rounded = if value < 10.0 {
+            (value * 10.0).ceil() / 10.0
+        } else {
+            value.ceil()
+        };
+        // Rounding up may reach the next unit, e.g. 1023.9K is 1.0M
+        if rounded >= 1024.0 && unit + 1 < UNITS.len() {
+            value /= 1024.0;
+            unit += 1;
+        } else if rounded < 10.0 {
+            return format!("{rounded:.1}{}", UNITS[unit]);
+        } else {
+            return format!("{rounded:.0}{}", UNITS[unit]);
+        }
+    }
+}
+
+/// Nested objects with the name, kind and size of every entry, and the
+/// contents of directories
+impl Serialize for DirectoryTree {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.sized().serialize(serializer)
+    }
+}
+
+impl Serialize for SizedTree<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.tree {
+            DirectoryTree::File(name, size) => {
+                let mut file = serializer.serialize_struct("File", 3)?;
+                file.serialize_field("name", name)?;
+                file.serialize_field("type", "file")?;
+                file.serialize_field("size", size)?;
+                file.end()
+            }
+            DirectoryTree::Directory(name, _) => {
+                let mut dir = serializer.serialize_struct("Directory", 4)?;
+                dir.serialize_field("name", name)?;
+                dir.serialize_field("type", "directory")?;
+                dir.serialize_field("size", &self.size)?;
+                dir.serialize_field("contents", &self.contents)?;
+                dir.end()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    use super::*;
+    use crate::tests::{replay, tree};
+
+    #[rstest]
+    fn test_tree(tree: DirectoryTree) {
+        assert_eq!(
+            Tree(&tree).to_string(),
+            indoc! {"
+                / (48381165)
+                ├── a (94853)
+                │   ├── e (584)
+                │   │   └── i (584)
+                │   ├── f (29116)
+                │   ├── g (2557)
+                │   └── h.lst (62596)
+                ├── b.txt (14848514)
+                ├── c.dat (8504156)
+                └── d (24933642)
+                    ├── j (4060174)
+                    ├── d.log (8033020)
+                    ├── d.ext (5626152)
+                    └── k (7214296)
+            "}
+        );
+    }
+
+    #[rstest]
+    fn test_du(tree: DirectoryTree) {
+        assert_eq!(
+            Du(&tree).to_string(),
+            "47M\t/\n24M\t/d\n93K\t/a\n584\t/a/e\n"
+        );
+    }
+
+    #[rstest]
+    #[case(0, "0")]
+    #[case(1023, "1023")]
+    #[case(1024, "1.0K")]
+    #[case(1025, "1.1K")]
+    #[case(10 * 1024, "10K")]
+    #[case(10 * 1024 - 1, "10K")]
+    #[case(5 * 1024 * 1024 * 1024, "5.0G")]
+    #[case(1024 * 1024 - 1, "1.0M")]
+    #[case(1024 * 1024, "1.0M")]
+    #[case(1024 * 1024 + 1, "1.1M")]
+    #[case(1024 * 1024 * 1024 - 1, "1.0G")]
+    #[case(1000 * 1024, "1000K")]
+    #[case(1023 * 1024 + 512, "1.0M")]
+    fn test_human(#[case] size: usize, #[case] expected: &str) {
+        assert_eq!(human(size), expected);
+    }
+
+    #[rstest]
+    fn test_json() {
+        let tree = replay("$ ls\n3 a\n").unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&tree).unwrap(),
+            r#"{"name":"/","type":"directory","size":3,"contents":[{"name":"a","type":"file","size":3}]}"#
+        );
+    }
+}
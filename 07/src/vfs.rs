@@ -0,0 +1,789 @@
+use std::{collections::HashMap, fmt};
+
+use color_eyre::{
+    eyre::{eyre, ContextCompat},
+    Result,
+};
+
+use super::*;
+
+/// Handle to a file or directory of a [`FileSystem`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    File,
+    Directory,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::File => write!(f, "file"),
+            Kind::Directory => write!(f, "directory"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stat {
+    pub path: String,
+    pub kind: Kind,
+    /// Size of the file, or total size of the directory contents
+    pub size: usize,
+    /// Number of direct children of a directory
+    pub entries: usize,
+}
+
+impl fmt::Display for Stat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}, {} bytes", self.path, self.kind, self.size)?;
+        if self.kind == Kind::Directory {
+            write!(f, ", {} entries", self.entries)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    name: String,
+    parent: Option<NodeId>,
+    /// Size of the file, or cached total size of the directory contents
+    size: usize,
+    children: Option<HashMap<String, NodeId>>,
+}
+
+/// Arena-backed filesystem
+///
+/// Nodes link to their parent and index their children by name, and
+/// directories cache the size of their contents, updated on every mutation
+/// along the path to the root.
+#[derive(Debug, Clone)]
+pub struct FileSystem {
+    /// Removed nodes leave a hole so that handles stay valid
+    nodes: Vec<Option<Node>>,
+}
+
+impl Default for FileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileSystem {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![Some(Node {
+                name: "/".into(),
+                parent: None,
+                size: 0,
+                children: Some(HashMap::new()),
+            })],
+        }
+    }
+
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    fn node(&self, id: NodeId) -> Result<&Node> {
+        self.nodes
+            .get(id.0)
+            .and_then(Option::as_ref)
+            .wrap_err_with(|| format!("No such node #{}", id.0))
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> Result<&mut Node> {
+        self.nodes
+            .get_mut(id.0)
+            .and_then(Option::as_mut)
+            .wrap_err_with(|| format!("No such node #{}", id.0))
+    }
+
+    /// Whether a handle still refers to a node, i.e. it was not removed
+    pub fn exists(&self, id: NodeId) -> bool {
+        self.node(id).is_ok()
+    }
+
+    pub fn name(&self, id: NodeId) -> Result<&str> {
+        Ok(&self.node(id)?.name)
+    }
+
+    pub fn size(&self, id: NodeId) -> Result<usize> {
+        Ok(self.node(id)?.size)
+    }
+
+    pub fn parent(&self, id: NodeId) -> Result<Option<NodeId>> {
+        Ok(self.node(id)?.parent)
+    }
+
+    pub fn kind(&self, id: NodeId) -> Result<Kind> {
+        Ok(match self.node(id)?.children {
+            Some(_) => Kind::Directory,
+            None => Kind::File,
+        })
+    }
+
+    pub fn is_dir(&self, id: NodeId) -> Result<bool> {
+        Ok(self.kind(id)? == Kind::Directory)
+    }
+
+    pub fn child(&self, id: NodeId, name: &str) -> Result<Option<NodeId>> {
+        Ok(self
+            .node(id)?
+            .children
+            .as_ref()
+            .and_then(|c| c.get(name).copied()))
+    }
+
+    /// Children of a directory, in no particular order
+    pub fn children(&self, id: NodeId) -> Result<impl Iterator<Item = NodeId> + '_> {
+        Ok(self
+            .node(id)?
+            .children
+            .iter()
+            .flat_map(|c| c.values().copied()))
+    }
+
+    /// Absolute path of a node
+    pub fn path(&self, id: NodeId) -> Result<String> {
+        let mut names = vec![];
+        let mut current = id;
+        while let Some(parent) = self.parent(current)? {
+            names.push(self.name(current)?);
+            current = parent;
+        }
+        names.reverse();
+        Ok(format!("/{}", names.join("/")))
+    }
+
+    /// Depth-first iterator over a node and everything below it
+    pub fn walk(&self, id: NodeId) -> Result<impl Iterator<Item = NodeId> + '_> {
+        self.node(id)?;
+        let mut stack = vec![id];
+        Ok(std::iter::from_fn(move || {
+            let id = stack.pop()?;
+            stack.extend(self.children(id).into_iter().flatten());
+            Some(id)
+        }))
+    }
+
+    /// Resolves a path, relative to `from` unless it starts with `/`
+    pub fn resolve_from(&self, from: NodeId, path: &str) -> Result<NodeId> {
+        let mut current = if path.starts_with('/') {
+            self.root()
+        } else {
+            from
+        };
+        for segment in path.split('/') {
+            current = match segment {
+                "" | "." => current,
+                ".." => self.parent(current)?.unwrap_or(current),
+                name => {
+                    if !self.is_dir(current)? {
+                        return Err(eyre!("Not a directory '{}'", self.path(current)?));
+                    }
+                    self.child(current, name)?
+                        .wrap_err_with(|| format!("No such file or directory '{path}'"))?
+                }
+            };
+        }
+        Ok(current)
+    }
+
+    pub fn resolve(&self, path: &str) -> Result<NodeId> {
+        self.resolve_from(self.root(), path)
+    }
+
+    pub fn stat(&self, path: &str) -> Result<Stat> {
+        let id = self.resolve(path)?;
+        Ok(Stat {
+            path: self.path(id)?,
+            kind: self.kind(id)?,
+            size: self.size(id)?,
+            entries: self.children(id)?.count(),
+        })
+    }
+
+    /// Adds an empty directory to `parent`
+    pub fn insert_dir(&mut self, parent: NodeId, name: &str) -> Result<NodeId> {
+        self.insert(parent, name, 0, Some(HashMap::new()))
+    }
+
+    /// Adds a file to `parent`
+    pub fn insert_file(&mut self, parent: NodeId, name: &str, size: usize) -> Result<NodeId> {
+        self.insert(parent, name, size, None)
+    }
+
+    fn insert(
+        &mut self,
+        parent: NodeId,
+        name: &str,
+        size: usize,
+        children: Option<HashMap<String, NodeId>>,
+    ) -> Result<NodeId> {
+        check_name(name)?;
+        if !self.is_dir(parent)? {
+            return Err(eyre!("Not a directory '{}'", self.path(parent)?));
+        }
+        if self.child(parent, name)?.is_some() {
+            return Err(eyre!("'{name}' already exists in '{}'", self.path(parent)?));
+        }
+
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Some(Node {
+            name: name.into(),
+            parent: None,
+            size,
+            children,
+        }));
+        if let Err(err) = self.attach(parent, id) {
+            self.nodes.pop();
+            return Err(err);
+        }
+        Ok(id)
+    }
+
+    /// Changes the size of a file
+    pub fn set_size(&mut self, id: NodeId, size: usize) -> Result<()> {
+        if self.is_dir(id)? {
+            return Err(eyre!("Not a file '{}'", self.path(id)?));
+        }
+        let (old, parent) = (self.size(id)?, self.parent(id)?);
+        if size >= old {
+            self.grow(parent, size - old)?;
+        } else {
+            self.shrink(parent, old - size)?;
+        }
+        self.node_mut(id)?.size = size;
+        Ok(())
+    }
+
+    /// Links a detached node to a directory, adding its size to the ancestors
+    fn attach(&mut self, parent: NodeId, id: NodeId) -> Result<()> {
+        self.grow(Some(parent), self.size(id)?)?;
+        let name = self.name(id)?.to_string();
+        self.node_mut(id)?.parent = Some(parent);
+        self.node_mut(parent)?
+            .children
+            .as_mut()
+            .expect("Parent is a directory")
+            .insert(name, id);
+        Ok(())
+    }
+
+    /// Unlinks a node from its parent, removing its size from the ancestors
+    fn detach(&mut self, id: NodeId) -> Result<()> {
+        let parent = self.parent(id)?.expect("Root cannot be detached");
+        self.shrink(Some(parent), self.size(id)?)?;
+        let name = self.name(id)?.to_string();
+        self.node_mut(parent)?
+            .children
+            .as_mut()
+            .expect("Parent is a directory")
+            .remove(&name);
+        self.node_mut(id)?.parent = None;
+        Ok(())
+    }
+
+    /// Adds `size` to a directory and its ancestors
+    fn grow(&mut self, dir: Option<NodeId>, size: usize) -> Result<()> {
+        self.resize(dir, |s| s.checked_add(size), "overflows")
+    }
+
+    /// Removes `size` from a directory and its ancestors
+    fn shrink(&mut self, dir: Option<NodeId>, size: usize) -> Result<()> {
+        self.resize(dir, |s| s.checked_sub(size), "underflows")
+    }
+
+    /// Updates the cached size of a directory and its ancestors, leaving them
+    /// all unchanged if any of them would not fit
+    fn resize(
+        &mut self,
+        dir: Option<NodeId>,
+        update: impl Fn(usize) -> Option<usize>,
+        failure: &str,
+    ) -> Result<()> {
+        let mut sizes = vec![];
+        let mut current = dir;
+        while let Some(id) = current {
+            let Some(size) = update(self.size(id)?) else {
+                return Err(eyre!("Size of '{}' {failure}", self.path(id)?));
+            };
+            sizes.push((id, size));
+            current = self.parent(id)?;
+        }
+        for (id, size) in sizes {
+            self.node_mut(id)?.size = size;
+        }
+        Ok(())
+    }
+
+    /// Splits a path into its existing parent directory and the last name
+    fn split<'a>(&self, path: &'a str) -> Result<(NodeId, &'a str)> {
+        let path = path.trim_end_matches('/');
+        let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+        let parent = self.resolve(dir)?;
+        Ok((parent, name))
+    }
+
+    /// Creates a directory, whose parent must exist
+    pub fn mkdir(&mut self, path: &str) -> Result<NodeId> {
+        let (parent, name) = self.split(path)?;
+        self.insert_dir(parent, name)
+    }
+
+    /// Creates a directory along with its missing parents, keeping the
+    /// existing ones
+    pub fn mkdir_all(&mut self, path: &str) -> Result<NodeId> {
+        let mut dir = self.root();
+        for segment in path.split('/') {
+            dir = match segment {
+                "" | "." => dir,
+                ".." => self.parent(dir)?.unwrap_or(dir),
+                name => match self.child(dir, name)? {
+                    Some(id) if self.is_dir(id)? => id,
+                    Some(id) => return Err(eyre!("Not a directory '{}'", self.path(id)?)),
+                    None => self.insert_dir(dir, name)?,
+                },
+            };
+        }
+        Ok(dir)
+    }
+
+    /// Creates a file, whose parent directory must exist
+    pub fn create(&mut self, path: &str, size: usize) -> Result<NodeId> {
+        let (parent, name) = self.split(path)?;
+        self.insert_file(parent, name, size)
+    }
+
+    /// Removes a file or a directory with all its contents
+    pub fn rm(&mut self, path: &str) -> Result<()> {
+        let id = self.resolve(path)?;
+        if id == self.root() {
+            return Err(eyre!("Cannot remove the root directory"));
+        }
+
+        self.detach(id)?;
+        let removed: Vec<_> = self.walk(id)?.collect();
+        for id in removed {
+            self.nodes[id.0] = None;
+        }
+        Ok(())
+    }
+
+    /// Moves a node into an existing directory, or renames it otherwise
+    pub fn mv(&mut self, from: &str, to: &str) -> Result<NodeId> {
+        let id = self.resolve(from)?;
+        if id == self.root() {
+            return Err(eyre!("Cannot move the root directory"));
+        }
+        let (parent, name) = match self.resolve(to) {
+            Ok(dir) if self.is_dir(dir)? => (dir, self.name(id)?.to_string()),
+            Ok(_) => return Err(eyre!("'{to}' already exists")),
+            Err(_) => {
+                let (parent, name) = self.split(to)?;
+                check_name(name)?;
+                (parent, name.to_string())
+            }
+        };
+
+        if !self.is_dir(parent)? {
+            return Err(eyre!("Not a directory '{}'", self.path(parent)?));
+        }
+        if self.ancestors(parent).any(|a| a == id) {
+            return Err(eyre!("Cannot move '{from}' into itself"));
+        }
+        if self.child(parent, &name)?.is_some() {
+            return Err(eyre!("'{name}' already exists in '{}'", self.path(parent)?));
+        }
+
+        self.detach(id)?;
+        self.node_mut(id)?.name = name;
+        self.attach(parent, id)?;
+        Ok(id)
+    }
+
+    /// A node followed by its parents up to the root
+    fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(Some(id), |&id| self.parent(id).ok().flatten())
+    }
+
+    /// Absolute form of a path relative to `from`
+    fn absolute(&self, from: NodeId, path: &str) -> Result<String> {
+        if path.starts_with('/') {
+            return Ok(path.into());
+        }
+        let dir = self.path(from)?;
+        Ok(format!("{}/{path}", dir.trim_end_matches('/')))
+    }
+
+    /// Runs a command of a terminal log in `cwd`, returning the new current
+    /// directory
+    fn run(&mut self, cwd: NodeId, command: terminal::Command) -> Result<NodeId> {
+        match command {
+            terminal::Command::Cd(path) => {
+                let dir = self.resolve_from(cwd, &path.to_string())?;
+                if !self.is_dir(dir)? {
+                    return Err(eyre!("Not a directory '{path}'"));
+                }
+                Ok(dir)
+            }
+            terminal::Command::Ls(listing) => {
+                for entry in listing {
+                    self.list(cwd, entry)?;
+                }
+                Ok(cwd)
+            }
+            terminal::Command::Mkdir { path, parents } => {
+                let path = self.absolute(cwd, &path.to_string())?;
+                if parents {
+                    self.mkdir_all(&path)?;
+                } else {
+                    self.mkdir(&path)?;
+                }
+                Ok(cwd)
+            }
+            terminal::Command::Rm {
+                path,
+                recursive,
+                force,
+            } => {
+                let id = match self.resolve_from(cwd, &path.to_string()) {
+                    Ok(id) => id,
+                    Err(_) if force => return Ok(cwd),
+                    Err(err) => return Err(err),
+                };
+                if !recursive && self.is_dir(id)? {
+                    return Err(eyre!("Cannot remove directory '{path}' without -r"));
+                }
+                let parent = self.parent(id)?;
+                self.rm(&self.path(id)?)?;
+                // The current directory may have been removed along the way
+                Ok(match parent {
+                    Some(parent) if !self.exists(cwd) => parent,
+                    _ => cwd,
+                })
+            }
+            terminal::Command::Du(_) => Ok(cwd),
+        }
+    }
+
+    /// Adds a listed entry to `dir`, keeping directories listed before and
+    /// updating the size of files
+    fn list(&mut self, dir: NodeId, entry: terminal::DirectoryEntry) -> Result<()> {
+        let name = match &entry {
+            terminal::DirectoryEntry::Dir(name) | terminal::DirectoryEntry::File(name, _) => name,
+        };
+        let existing = self.child(dir, name)?;
+        match (existing, entry) {
+            (None, terminal::DirectoryEntry::Dir(name)) => {
+                self.insert_dir(dir, &name)?;
+            }
+            (None, terminal::DirectoryEntry::File(name, size)) => {
+                self.insert_file(dir, &name, size)?;
+            }
+            (Some(id), terminal::DirectoryEntry::Dir(_)) if self.is_dir(id)? => {}
+            (Some(id), terminal::DirectoryEntry::File(_, size)) if !self.is_dir(id)? => {
+                self.set_size(id, size)?;
+            }
+            (Some(id), _) => {
+                return Err(eyre!(
+                    "'{}' listed both as a file and as a directory",
+                    self.name(id)?
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies a node and everything below it, listing the contents of
+    /// directories in the order they were created
+    pub fn to_tree(&self, id: NodeId) -> Result<DirectoryTree> {
+        let name = self.name(id)?.to_string();
+        if !self.is_dir(id)? {
+            return Ok(DirectoryTree::File(name, self.size(id)?));
+        }
+        let mut children: Vec<_> = self.children(id)?.collect();
+        children.sort_by_key(|c| c.0);
+        let contents = children
+            .into_iter()
+            .map(|c| self.to_tree(c))
+            .collect::<Result<_>>()?;
+        Ok(DirectoryTree::Directory(name, contents))
+    }
+}
+
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+        return Err(eyre!("Invalid name '{name}'"));
+    }
+    Ok(())
+}
+
+/// Replays a terminal log from the root directory
+impl TryFrom<Vec<terminal::Command>> for FileSystem {
+    type Error = Report;
+
+    fn try_from(commands: Vec<terminal::Command>) -> Result<Self> {
+        let mut fs = Self::new();
+        let mut cwd = fs.root();
+        for command in commands {
+            cwd = fs.run(cwd, command)?;
+        }
+        Ok(fs)
+    }
+}
+
+impl TryFrom<&FileSystem> for DirectoryTree {
+    type Error = Report;
+
+    fn try_from(fs: &FileSystem) -> Result<Self> {
+        fs.to_tree(fs.root())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    use super::*;
+
+    /// Filesystem of the example terminal output
+    #[fixture]
+    fn fs() -> FileSystem {
+        let mut fs = FileSystem::new();
+        for dir in ["/a", "/a/e", "/d"] {
+            fs.mkdir(dir).unwrap();
+        }
+        for (file, size) in [
+            ("/b.txt", 14848514),
+            ("/c.dat", 8504156),
+            ("/a/f", 29116),
+            ("/a/g", 2557),
+            ("/a/h.lst", 62596),
+            ("/a/e/i", 584),
+            ("/d/j", 4060174),
+            ("/d/d.log", 8033020),
+            ("/d/d.ext", 5626152),
+            ("/d/k", 7214296),
+        ] {
+            fs.create(file, size).unwrap();
+        }
+        fs
+    }
+
+    #[rstest]
+    #[case("/", 48381165)]
+    #[case("/a", 94853)]
+    #[case("/a/e", 584)]
+    #[case("/a/e/i", 584)]
+    #[case("/d", 24933642)]
+    #[case("/a/e/../../d/./k", 7214296)]
+    fn test_sizes(fs: FileSystem, #[case] path: &str, #[case] size: usize) {
+        assert_eq!(fs.stat(path).unwrap().size, size);
+    }
+
+    #[rstest]
+    fn test_resolve(fs: FileSystem) {
+        let e = fs.resolve("/a/e").unwrap();
+
+        assert_eq!(fs.path(e).unwrap(), "/a/e");
+        assert_eq!(fs.path(fs.resolve_from(e, "i").unwrap()).unwrap(), "/a/e/i");
+        assert_eq!(
+            fs.path(fs.resolve_from(e, "../f").unwrap()).unwrap(),
+            "/a/f"
+        );
+        assert_eq!(fs.resolve_from(e, "/").unwrap(), fs.root());
+        assert_eq!(
+            fs.resolve("/a/x").unwrap_err().to_string(),
+            "No such file or directory '/a/x'"
+        );
+        assert_eq!(
+            fs.resolve("/b.txt/x").unwrap_err().to_string(),
+            "Not a directory '/b.txt'"
+        );
+    }
+
+    #[rstest]
+    fn test_stat(fs: FileSystem) {
+        assert_eq!(
+            fs.stat("/a").unwrap(),
+            Stat {
+                path: "/a".into(),
+                kind: Kind::Directory,
+                size: 94853,
+                entries: 4,
+            }
+        );
+        assert_eq!(
+            fs.stat("/a/f").unwrap().to_string(),
+            "/a/f: file, 29116 bytes"
+        );
+    }
+
+    #[rstest]
+    fn test_mutations(mut fs: FileSystem) {
+        fs.rm("/a/e").unwrap();
+        assert_eq!(fs.size(fs.root()).unwrap(), 48381165 - 584);
+        assert!(fs.resolve("/a/e/i").is_err());
+
+        fs.mv("/d/k", "/a").unwrap();
+        assert_eq!(fs.stat("/a").unwrap().size, 94853 - 584 + 7214296);
+        assert_eq!(fs.stat("/d").unwrap().size, 24933642 - 7214296);
+
+        fs.mv("/a", "/d/z").unwrap();
+        assert_eq!(fs.stat("/d/z/k").unwrap().path, "/d/z/k");
+        assert_eq!(fs.stat("/d").unwrap().size, 24933642 + 94853 - 584);
+        assert_eq!(fs.size(fs.root()).unwrap(), 48381165 - 584);
+    }
+
+    #[rstest]
+    fn test_removed_node(mut fs: FileSystem) {
+        let e = fs.resolve("/a/e").unwrap();
+        let i = fs.resolve("/a/e/i").unwrap();
+        fs.rm("/a/e").unwrap();
+
+        assert!(!fs.exists(i));
+        assert_eq!(
+            fs.size(i).unwrap_err().to_string(),
+            format!("No such node #{}", i.0)
+        );
+        assert!(fs.path(e).is_err());
+        assert!(fs.insert_file(e, "x", 1).is_err());
+    }
+
+    #[rstest]
+    fn test_size_overflow(mut fs: FileSystem) {
+        assert_eq!(
+            fs.create("/a/x", usize::MAX).unwrap_err().to_string(),
+            "Size of '/a' overflows"
+        );
+        assert!(fs.resolve("/a/x").is_err());
+        assert_eq!(fs.stat("/a").unwrap().size, 94853);
+
+        let f = fs.resolve("/a/f").unwrap();
+        assert!(fs.set_size(f, usize::MAX - 1).is_err());
+        assert_eq!(fs.size(f).unwrap(), 29116);
+        assert_eq!(fs.size(fs.root()).unwrap(), 48381165);
+
+        fs.set_size(f, 0).unwrap();
+        assert_eq!(fs.stat("/a").unwrap().size, 94853 - 29116);
+    }
+
+    #[rstest]
+    fn test_replay(fs: FileSystem) {
+        let log = indoc! {"
+            $ cd /
+            $ ls
+            dir a
+            14848514 b.txt
+            8504156 c.dat
+            dir d
+            $ cd a
+            $ ls
+            dir e
+            29116 f
+            2557 g
+            62596 h.lst
+            $ cd e
+            $ ls
+            584 i
+            $ cd /d
+            $ ls
+            4060174 j
+            8033020 d.log
+            5626152 d.ext
+            7214296 k
+        "};
+        let replayed = FileSystem::try_from(terminal::parse(log).unwrap()).unwrap();
+
+        for path in ["/", "/a", "/a/e", "/d", "/d/k"] {
+            assert_eq!(replayed.stat(path).unwrap(), fs.stat(path).unwrap());
+        }
+    }
+
+    #[rstest]
+    fn test_replay_mutations() {
+        let log = indoc! {"
+            $ mkdir a
+            $ cd a
+            $ mkdir -p ./b
+            $ cd b
+            $ ls
+            10 x
+            $ ls
+            12 x
+            $ rm -r /a/b
+            $ ls
+            3 y
+        "};
+        let fs = FileSystem::try_from(terminal::parse(log).unwrap()).unwrap();
+
+        assert_eq!(fs.stat("/").unwrap().size, 3);
+        assert_eq!(fs.stat("/a/y").unwrap().size, 3);
+        assert!(fs.resolve("/a/b").is_err());
+    }
+
+    #[rstest]
+    fn test_replay_flags() {
+        let log = indoc! {"
+            $ mkdir -p /x/y/../z
+            $ mkdir -p x/y
+            $ rm -f /x/missing
+            $ cd /x/y
+            $ ls
+            5 a
+            $ rm a
+            $ rm -r ../z
+            $ cd /"};
+        let fs = FileSystem::try_from(terminal::parse(log).unwrap()).unwrap();
+
+        assert_eq!(fs.stat("/x").unwrap().entries, 1);
+        assert_eq!(fs.stat("/x/y").unwrap().entries, 0);
+    }
+
+    #[rstest]
+    #[case("$ mkdir x\n$ mkdir x\n", "'x' already exists in '/'")]
+    #[case("$ mkdir x/y\n", "No such file or directory '/x'")]
+    #[case("$ rm x\n", "No such file or directory 'x'")]
+    #[case("$ mkdir x\n$ rm -f x\n", "Cannot remove directory 'x' without -r")]
+    #[case("$ ls\n1 x\n$ mkdir -p x/y\n", "Not a directory '/x'")]
+    fn test_replay_errors(#[case] log: &str, #[case] expected: &str) {
+        let err = FileSystem::try_from(terminal::parse(log).unwrap()).unwrap_err();
+
+        assert_eq!(err.root_cause().to_string(), expected);
+    }
+
+    #[rstest]
+    fn test_to_tree(fs: FileSystem) {
+        let tree = DirectoryTree::try_from(&fs).unwrap();
+
+        assert_eq!(tree.size(), 48381165);
+        assert_eq!(
+            tree.iter_dirs()
+                .map(DirectoryTree::name)
+                .collect::<Vec<_>>(),
+            ["/", "a", "e", "d"]
+        );
+    }
+
+    #[rstest]
+    #[case(|fs: &mut FileSystem| fs.mkdir("/a").map(|_| ()), "'a' already exists in '/'")]
+    #[case(|fs: &mut FileSystem| fs.mkdir("/x/y").map(|_| ()), "No such file or directory '/x'")]
+    #[case(|fs: &mut FileSystem| fs.create("/b.txt/y", 1).map(|_| ()), "Not a directory '/b.txt'")]
+    #[case(|fs: &mut FileSystem| fs.rm("/"), "Cannot remove the root directory")]
+    #[case(|fs: &mut FileSystem| fs.mv("/a", "/a/e").map(|_| ()), "Cannot move '/a' into itself")]
+    #[case(|fs: &mut FileSystem| fs.mv("/a/f", "/c.dat").map(|_| ()), "'/c.dat' already exists")]
+    fn test_errors(
+        mut fs: FileSystem,
+        #[case] op: fn(&mut FileSystem) -> Result<()>,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(op(&mut fs).unwrap_err().to_string(), expected);
+    }
+}
@@ -1,14 +1,90 @@
+use std::{fmt::Display, str::FromStr};
+
 use color_eyre::{
-    eyre::{ContextCompat, WrapErr},
+    eyre::{eyre, ContextCompat, WrapErr},
     Result,
 };
 
 pub fn init() -> Result<String> {
+    setup()?;
+
+    let path = input_path()?;
+    std::fs::read_to_string(&path).wrap_err_with(|| format!("Failed to read from {:?}", path))
+}
+
+/// Installs the error reporting hooks without reading the input
+pub fn setup() -> Result<()> {
     std::env::set_var("RUST_BACKTRACE", "full");
-    color_eyre::install()?;
+    color_eyre::install()
+}
 
-    let path = std::env::args()
+/// Returns the `<input file>` argument, for days streaming their input
+pub fn input_path() -> Result<String> {
+    std::env::args()
         .nth(1)
-        .wrap_err("Missing argument: <input file>")?;
-    std::fs::read_to_string(&path).wrap_err_with(|| format!("Failed to read from {:?}", path))
+        .wrap_err("Missing argument: <input file>")
+}
+
+/// Lines of the input numbered from 1, without the blank lines at its end
+///
+/// Other lines are kept as they are, so that parsers see the same text
+/// wherever a line is.
+pub fn lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    let end = input
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .last()
+        .map_or(0, |(n, _)| n + 1);
+    input.lines().take(end).zip(1..).map(|(l, n)| (n, l))
+}
+
+/// Returns the command line options following `<input file>`
+pub fn options() -> Options {
+    Options(std::env::args().skip(2).collect())
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Options(Vec<String>);
+
+impl Options {
+    pub fn flag(&self, name: &str) -> bool {
+        self.0.iter().any(|a| a == name)
+    }
+
+    /// Looks up the value of `--name value` or `--name=value`
+    pub fn value(&self, name: &str) -> Option<&str> {
+        let mut args = self.0.iter();
+        while let Some(arg) = args.next() {
+            if arg == name {
+                return args.next().map(String::as_str);
+            }
+            if let Some(value) = arg
+                .strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix('='))
+            {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    pub fn parse<T>(&self, name: &str) -> Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.value(name)
+            .map(|v| {
+                v.parse()
+                    .map_err(|err| eyre!("Invalid value {v:?} for {name}: {err}"))
+            })
+            .transpose()
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for Options {
+    fn from_iter<T: IntoIterator<Item = S>>(iter: T) -> Self {
+        Self(iter.into_iter().map(Into::into).collect())
+    }
 }